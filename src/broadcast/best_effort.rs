@@ -23,7 +23,8 @@ impl BestEffort {
     ) -> Self
     where
         M: Message + Clone,
-        R: IntoIterator<Item = Identity>,
+        R: IntoIterator,
+        R::Item: Into<Identity>,
     {
         BestEffort::setup(sender, remotes, message, None, settings)
     }
@@ -37,7 +38,8 @@ impl BestEffort {
     ) -> Self
     where
         M: Message + Clone,
-        R: IntoIterator<Item = Identity>,
+        R: IntoIterator,
+        R::Item: Into<Identity>,
     {
         BestEffort::setup(sender, remotes, brief, Some(expanded), settings)
     }
@@ -51,11 +53,13 @@ impl BestEffort {
     ) -> Self
     where
         M: Message + Clone,
-        R: IntoIterator<Item = Identity>,
+        R: IntoIterator,
+        R::Item: Into<Identity>,
     {
         let unordered = remotes
             .into_iter()
             .map(|remote| {
+                let remote = remote.into();
                 let sender = sender.clone();
                 let message = message.clone();
                 let fallback = fallback.clone();
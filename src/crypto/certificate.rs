@@ -0,0 +1,423 @@
+use crate::crypto::{
+    primitives::multi::Signature as MultiSignature, procedures, Committee, Identity, KeyCard,
+    Statement,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+};
+
+/// A quorum certificate for a `Statement` of type `S`.
+///
+/// A `Certificate` pairs an aggregate `MultiSignature` with a bitmap
+/// of its signers, relative to the order of the `Committee` it was
+/// `aggregate`d for. The same `Committee` must be provided to `verify`.
+pub struct Certificate<S> {
+    signers: Vec<u8>,
+    signature: MultiSignature,
+    _statement: PhantomData<S>,
+}
+
+#[derive(Doom)]
+pub enum CertificateError {
+    #[doom(description("Certificate has no valid signer"))]
+    EmptyCertificate,
+    #[doom(description("Certificate does not meet the required threshold"))]
+    InsufficientSigners,
+    #[doom(description("Certificate signature is invalid"))]
+    InvalidSignature,
+    #[doom(description("Signer bitmap does not match the committee"))]
+    MalformedSigners,
+}
+
+impl<S> Certificate<S>
+where
+    S: Statement,
+{
+    /// Aggregates `entries` into a `Certificate` for `statement`.
+    ///
+    /// Entries whose signer does not belong to `committee` are ignored, as
+    /// are repeated entries for the same signer. Invalid signatures are
+    /// filtered out by `procedures::filter_aggregate`.
+    ///
+    /// # Errors
+    ///
+    /// If no valid signature remains, an `EmptyCertificate` error variant
+    /// will be returned.
+    pub fn aggregate<'a, E>(
+        committee: &Committee,
+        statement: &S,
+        entries: E,
+    ) -> Result<Self, Top<CertificateError>>
+    where
+        S: Sync,
+        E: IntoIterator<Item = (Identity, &'a MultiSignature)>,
+    {
+        let mut signers = vec![0u8; bitmap_length(committee.len())];
+        let mut shares = Vec::new();
+
+        for (identity, signature) in entries {
+            if let Some(index) = committee.index_of(identity) {
+                if !get_bit(&signers, index) {
+                    set_bit(&mut signers, index, true);
+                    shares.push((index, signature));
+                }
+            }
+        }
+
+        let (signature, exceptions) = procedures::filter_aggregate(
+            statement,
+            shares
+                .iter()
+                .map(|(index, signature)| (&committee.members()[*index], *signature)),
+        );
+
+        for exception in exceptions {
+            let (index, _) = shares[exception];
+            set_bit(&mut signers, index, false);
+        }
+
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return CertificateError::EmptyCertificate.fail().spot(here!()),
+        };
+
+        Ok(Certificate {
+            signers,
+            signature,
+            _statement: PhantomData,
+        })
+    }
+
+    /// Verifies that this `Certificate` carries the signatures of at least
+    /// `threshold` members of `committee` on `statement`.
+    pub fn verify(
+        &self,
+        committee: &Committee,
+        statement: &S,
+        threshold: usize,
+    ) -> Result<(), Top<CertificateError>> {
+        if !self.fits(committee.len()) {
+            return CertificateError::MalformedSigners.fail().spot(here!());
+        }
+
+        if self.power() < threshold {
+            return CertificateError::InsufficientSigners.fail().spot(here!());
+        }
+
+        self.signature
+            .verify(self.signers(committee), statement)
+            .pot(CertificateError::InvalidSignature, here!())
+    }
+}
+
+impl<S> Certificate<S> {
+    /// Returns the number of signers of this `Certificate`.
+    pub fn power(&self) -> usize {
+        self.signers
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the members of `committee` that signed this `Certificate`.
+    pub fn signers<'c>(&'c self, committee: &'c Committee) -> impl Iterator<Item = &'c KeyCard> {
+        committee
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| get_bit(&self.signers, *index))
+            .map(|(_, keycard)| keycard)
+    }
+
+    pub fn signature(&self) -> &MultiSignature {
+        &self.signature
+    }
+
+    // Checks that the signer bitmap is consistent with a committee of size
+    // `size`, i.e., has the correct length and no padding bit set
+    fn fits(&self, size: usize) -> bool {
+        self.signers.len() == bitmap_length(size)
+            && (size..self.signers.len() * 8).all(|index| !get_bit(&self.signers, index))
+    }
+}
+
+fn bitmap_length(size: usize) -> usize {
+    (size + 7) / 8
+}
+
+fn get_bit(bitmap: &[u8], index: usize) -> bool {
+    bitmap[index / 8] & (1 << (index % 8)) != 0
+}
+
+fn set_bit(bitmap: &mut [u8], index: usize, value: bool) {
+    if value {
+        bitmap[index / 8] |= 1 << (index % 8);
+    } else {
+        bitmap[index / 8] &= !(1 << (index % 8));
+    }
+}
+
+impl<S> Clone for Certificate<S> {
+    fn clone(&self) -> Self {
+        Certificate {
+            signers: self.signers.clone(),
+            signature: self.signature,
+            _statement: PhantomData,
+        }
+    }
+}
+
+impl<S> Debug for Certificate<S> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Certificate")
+            .field("power", &self.power())
+            .field("signature", &self.signature)
+            .finish()
+    }
+}
+
+impl<S> Serialize for Certificate<S> {
+    fn serialize<SE>(&self, serializer: SE) -> Result<SE::Ok, SE::Error>
+    where
+        SE: Serializer,
+    {
+        (&self.signers, &self.signature).serialize(serializer)
+    }
+}
+
+impl<'de, S> Deserialize<'de> for Certificate<S> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (signers, signature) = <(Vec<u8>, MultiSignature)>::deserialize(deserializer)?;
+
+        Ok(Certificate {
+            signers,
+            signature,
+            _statement: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyChain;
+    use std::iter;
+
+    #[derive(Serialize)]
+    enum TestStatement {
+        Correct,
+        Incorrect,
+    }
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    fn setup(size: usize) -> (Vec<KeyChain>, Committee) {
+        let mut keychains = iter::repeat_with(KeyChain::random)
+            .take(size)
+            .collect::<Vec<_>>();
+
+        keychains.sort_by_key(|keychain| keychain.keycard().identity());
+
+        let committee = Committee::new(keychains.iter().map(KeyChain::keycard));
+
+        (keychains, committee)
+    }
+
+    #[test]
+    fn correct() {
+        let (keychains, committee) = setup(10);
+
+        let signatures = keychains
+            .iter()
+            .map(|keychain| keychain.multisign(&TestStatement::Correct).unwrap())
+            .collect::<Vec<_>>();
+
+        let certificate = Certificate::aggregate(
+            &committee,
+            &TestStatement::Correct,
+            committee
+                .iter()
+                .map(KeyCard::identity)
+                .zip(signatures.iter()),
+        )
+        .unwrap();
+
+        assert_eq!(certificate.power(), 10);
+
+        certificate
+            .verify(&committee, &TestStatement::Correct, 7)
+            .unwrap();
+    }
+
+    #[test]
+    fn filter_incorrect() {
+        let (keychains, committee) = setup(10);
+
+        let signatures = keychains
+            .iter()
+            .enumerate()
+            .map(|(index, keychain)| {
+                keychain
+                    .multisign(if index % 3 == 0 {
+                        &TestStatement::Incorrect
+                    } else {
+                        &TestStatement::Correct
+                    })
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let certificate = Certificate::aggregate(
+            &committee,
+            &TestStatement::Correct,
+            committee
+                .iter()
+                .map(KeyCard::identity)
+                .zip(signatures.iter()),
+        )
+        .unwrap();
+
+        assert_eq!(certificate.power(), 6);
+
+        assert_eq!(
+            certificate.signers(&committee).cloned().collect::<Vec<_>>(),
+            committee
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| index % 3 != 0)
+                .map(|(_, keycard)| keycard.clone())
+                .collect::<Vec<_>>()
+        );
+
+        certificate
+            .verify(&committee, &TestStatement::Correct, 6)
+            .unwrap();
+
+        assert!(certificate
+            .verify(&committee, &TestStatement::Correct, 7)
+            .is_err());
+    }
+
+    #[test]
+    fn ignore_outsiders() {
+        let (keychains, committee) = setup(4);
+        let outsider = KeyChain::random();
+
+        let signatures = keychains
+            .iter()
+            .chain(iter::once(&outsider))
+            .map(|keychain| {
+                (
+                    keychain.keycard().identity(),
+                    keychain.multisign(&TestStatement::Correct).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let certificate = Certificate::aggregate(
+            &committee,
+            &TestStatement::Correct,
+            signatures
+                .iter()
+                .chain(signatures.iter())
+                .map(|(identity, signature)| (*identity, signature)),
+        )
+        .unwrap();
+
+        assert_eq!(certificate.power(), 4);
+
+        certificate
+            .verify(&committee, &TestStatement::Correct, 4)
+            .unwrap();
+    }
+
+    #[test]
+    fn empty() {
+        let (keychains, committee) = setup(4);
+
+        let signatures = keychains
+            .iter()
+            .map(|keychain| keychain.multisign(&TestStatement::Incorrect).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(Certificate::aggregate(
+            &committee,
+            &TestStatement::Correct,
+            committee
+                .iter()
+                .map(KeyCard::identity)
+                .zip(signatures.iter()),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn compromise_signers() {
+        let (keychains, committee) = setup(10);
+
+        let signatures = keychains
+            .iter()
+            .take(7)
+            .map(|keychain| keychain.multisign(&TestStatement::Correct).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut certificate = Certificate::aggregate(
+            &committee,
+            &TestStatement::Correct,
+            committee
+                .iter()
+                .map(KeyCard::identity)
+                .zip(signatures.iter()),
+        )
+        .unwrap();
+
+        set_bit(&mut certificate.signers, 8, true);
+
+        assert!(certificate
+            .verify(&committee, &TestStatement::Correct, 7)
+            .is_err());
+
+        set_bit(&mut certificate.signers, 8, false);
+        set_bit(&mut certificate.signers, 12, true);
+
+        assert!(certificate
+            .verify(&committee, &TestStatement::Correct, 7)
+            .is_err());
+    }
+
+    #[test]
+    fn serialize() {
+        let (keychains, committee) = setup(10);
+
+        let signatures = keychains
+            .iter()
+            .map(|keychain| keychain.multisign(&TestStatement::Correct).unwrap())
+            .collect::<Vec<_>>();
+
+        let certificate = Certificate::aggregate(
+            &committee,
+            &TestStatement::Correct,
+            committee
+                .iter()
+                .map(KeyCard::identity)
+                .zip(signatures.iter()),
+        )
+        .unwrap();
+
+        let bytes = bincode::serialize(&certificate).unwrap();
+        let certificate: Certificate<TestStatement> = bincode::deserialize(&bytes).unwrap();
+
+        certificate
+            .verify(&committee, &TestStatement::Correct, 10)
+            .unwrap();
+    }
+}
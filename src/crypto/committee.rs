@@ -0,0 +1,213 @@
+use crate::crypto::{
+    primitives::hash::{self, Hash},
+    Identity, KeyCard,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::slice::Iter;
+
+/// A set of `KeyCard`s, ordered by `Identity`.
+///
+/// Assuming that at most `f` out of `3f + 1` members are faulty, a
+/// `Committee` provides the `plurality` (`f + 1`) and `quorum` (`2f + 1`)
+/// thresholds, along with a position for each member that can be used
+/// to index signer sets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Committee {
+    members: Vec<KeyCard>,
+    hash: Hash,
+}
+
+impl Committee {
+    /// Builds a `Committee` out of `members`, sorting them by `Identity`
+    /// and discarding duplicates.
+    pub fn new<M>(members: M) -> Self
+    where
+        M: IntoIterator<Item = KeyCard>,
+    {
+        let mut members = members.into_iter().collect::<Vec<_>>();
+
+        members.sort();
+        members.dedup();
+
+        let identities = members.iter().map(KeyCard::identity).collect::<Vec<_>>();
+        let hash = hash::hash(&identities).unwrap();
+
+        Committee { members, hash }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the maximum number of faulty members tolerated by this `Committee`.
+    pub fn faults(&self) -> usize {
+        self.members.len().saturating_sub(1) / 3
+    }
+
+    /// Returns the smallest number of members that includes at least one
+    /// correct member (`f + 1`).
+    pub fn plurality(&self) -> usize {
+        self.faults() + 1
+    }
+
+    /// Returns the smallest number of members such that any two sets of
+    /// that size intersect in at least one correct member (`2f + 1` when
+    /// `len()` is `3f + 1`).
+    pub fn quorum(&self) -> usize {
+        self.members.len() - self.faults()
+    }
+
+    pub fn members(&self) -> &[KeyCard] {
+        self.members.as_slice()
+    }
+
+    pub fn iter(&self) -> Iter<KeyCard> {
+        self.members.iter()
+    }
+
+    pub fn identities(&self) -> impl Iterator<Item = Identity> + '_ {
+        self.members.iter().map(KeyCard::identity)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&KeyCard> {
+        self.members.get(index)
+    }
+
+    pub fn index_of(&self, identity: Identity) -> Option<usize> {
+        self.members
+            .binary_search_by_key(&identity, KeyCard::identity)
+            .ok()
+    }
+
+    pub fn contains(&self, identity: Identity) -> bool {
+        self.index_of(identity).is_some()
+    }
+
+    /// Returns the hash of the `Identity` of every member, in order.
+    ///
+    /// Two `Committee`s have the same hash if and only if they
+    /// have the same members.
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+}
+
+impl<'a> IntoIterator for &'a Committee {
+    type Item = &'a KeyCard;
+    type IntoIter = Iter<'a, KeyCard>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.members.iter()
+    }
+}
+
+impl Serialize for Committee {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.members.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Committee {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let members = Vec::<KeyCard>::deserialize(deserializer)?;
+        Ok(Committee::new(members))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{primitives::multi::Signature as MultiSignature, KeyChain, Statement};
+    use std::iter;
+
+    #[derive(Serialize)]
+    struct TestStatement;
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    fn keycards(size: usize) -> Vec<KeyCard> {
+        iter::repeat_with(KeyChain::random)
+            .take(size)
+            .map(|keychain| keychain.keycard())
+            .collect()
+    }
+
+    #[test]
+    fn thresholds() {
+        for (size, plurality, quorum) in [(1, 1, 1), (4, 2, 3), (5, 2, 4), (7, 3, 5), (10, 4, 7)] {
+            let committee = Committee::new(keycards(size));
+
+            assert_eq!(committee.plurality(), plurality);
+            assert_eq!(committee.quorum(), quorum);
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let keycards = keycards(16);
+
+        let committee = Committee::new(keycards.iter().rev().cloned().chain(keycards.clone()));
+
+        assert_eq!(committee.len(), 16);
+
+        for (index, keycard) in committee.iter().enumerate() {
+            assert_eq!(committee.index_of(keycard.identity()), Some(index));
+        }
+
+        assert!(committee
+            .identities()
+            .zip(committee.identities().skip(1))
+            .all(|(left, right)| left < right));
+
+        assert!(!committee.contains(KeyChain::random().keycard().identity()));
+    }
+
+    #[test]
+    fn stable_hash() {
+        let keycards = keycards(16);
+
+        let committee = Committee::new(keycards.clone());
+        let reversed = Committee::new(keycards.iter().rev().cloned());
+        let smaller = Committee::new(keycards.iter().skip(1).cloned());
+
+        assert_eq!(committee.hash(), reversed.hash());
+        assert_ne!(committee.hash(), smaller.hash());
+
+        let bytes = bincode::serialize(&committee).unwrap();
+        let deserialized: Committee = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(deserialized, committee);
+        assert_eq!(deserialized.hash(), committee.hash());
+    }
+
+    #[test]
+    fn verify_multisignature() {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(8)
+            .collect::<Vec<_>>();
+
+        let committee = Committee::new(keychains.iter().map(KeyChain::keycard));
+
+        let signature = MultiSignature::aggregate(
+            keychains
+                .iter()
+                .map(|keychain| keychain.multisign(&TestStatement).unwrap()),
+        )
+        .unwrap();
+
+        signature.verify(&committee, &TestStatement).unwrap();
+    }
+}
@@ -1,10 +1,18 @@
-use crate::crypto::primitives::hash::{Hash, HASH_LENGTH};
+use crate::crypto::primitives::{
+    hash::{Hash, HASH_LENGTH},
+    text::{self, TextError},
+};
+use doomstack::{here, Doom, ResultExt, Top};
 use serde::{Deserialize, Serialize};
 use std::{
+    convert::TryInto,
     fmt,
-    fmt::{Debug, Formatter},
+    fmt::{Debug, Display, Formatter},
+    str::FromStr,
 };
 
+const TEXT_PREFIX: &str = "id";
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Identity(Hash);
 
@@ -43,3 +51,22 @@ impl Debug for Identity {
         }
     }
 }
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(&text::encode(TEXT_PREFIX, &self.to_bytes()))
+    }
+}
+
+impl FromStr for Identity {
+    type Err = Top<TextError>;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = text::decode(TEXT_PREFIX, text)?;
+
+        match bytes.as_slice().try_into() {
+            Ok(bytes) => Ok(Identity::from_bytes(bytes)),
+            Err(_) => TextError::MalformedPayload.fail().spot(here!()),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::crypto::{
+    key_chain::KeyPairs,
     primitives::{
+        exchange::PublicKey as ExchangePublicKey,
         hash,
         multi::{
             MultiError, PublicKey as MultiPublicKey, Signature as MultiSignature,
@@ -8,20 +10,29 @@ use crate::crypto::{
         sign::{
             PublicKey as SignPublicKey, SignError, Signature as SignSignature, Signer as SignSigner,
         },
+        text::{self, TextError},
+        vrf::{Output as VrfOutput, Proof as VrfProof, VrfError},
     },
-    Identity, KeyChain, Statement,
+    Identity, KeyChain, Scope, Statement, TalkHeader,
 };
-use doomstack::Top;
+use doomstack::{here, Doom, ResultExt, Top};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     cmp::{Ord, Ordering, PartialOrd},
+    fmt,
+    fmt::{Display, Formatter},
     hash::{Hash, Hasher},
+    str::FromStr,
 };
 
+const TEXT_PREFIX: &str = "keycard";
+
 #[derive(Debug, Clone)]
 pub struct KeyCard {
     identity: Identity,
     keys: PublicKeys,
+    possession: MultiSignature,
+    encryption: Option<EncryptionKey>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,24 +41,150 @@ struct PublicKeys {
     multi: MultiPublicKey,
 }
 
+// An exchange `PublicKey`, bound to a `KeyCard` by a signature of its `sign` key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptionKey {
+    key: ExchangePublicKey,
+    signature: SignSignature,
+}
+
+#[derive(Serialize)]
+struct Encryption(ExchangePublicKey);
+
+#[derive(Doom)]
+pub enum KeyCardError {
+    #[doom(description("Invalid encryption key signature"))]
+    InvalidEncryptionKey,
+    #[doom(description("Invalid proof of possession"))]
+    InvalidPossession,
+}
+
 impl KeyCard {
     pub fn from_keychain(keychain: &KeyChain) -> Self {
-        KeyCard::from_public_keys(
-            keychain.keypairs.sign.public(),
-            keychain.keypairs.multi.public(),
-        )
+        keychain.keycard()
     }
 
-    pub fn from_public_keys(sign: SignPublicKey, multi: MultiPublicKey) -> Self {
+    pub(in crate::crypto) fn from_keypairs(keypairs: &KeyPairs) -> Self {
+        let key = keypairs.exchange_keypair().public();
+        let signature = keypairs.sign(&Encryption(key)).unwrap();
+
+        let mut keycard = KeyCard::from_keys(
+            keypairs.sign.public(),
+            keypairs.multi.public(),
+            keypairs.prove_possession(),
+        );
+
+        keycard.encryption = Some(EncryptionKey { key, signature });
+        keycard
+    }
+
+    /// Builds a `KeyCard` out of its public keys and the proof that
+    /// its holder knows the secret matching `multi`.
+    ///
+    /// # Errors
+    ///
+    /// If `possession` is not a valid proof of possession for `multi`,
+    /// an `InvalidPossession` error variant will be returned.
+    pub fn from_public_keys(
+        sign: SignPublicKey,
+        multi: MultiPublicKey,
+        possession: MultiSignature,
+    ) -> Result<Self, Top<KeyCardError>> {
+        let keycard = KeyCard::from_keys(sign, multi, possession);
+        keycard.verify_possession()?;
+
+        Ok(keycard)
+    }
+
+    fn from_keys(sign: SignPublicKey, multi: MultiPublicKey, possession: MultiSignature) -> Self {
         let keys = PublicKeys { sign, multi };
-        let identity = Identity::from_hash(hash::hash(&keys).unwrap());
+        let identity = keys.identity();
 
-        KeyCard { identity, keys }
+        KeyCard {
+            identity,
+            keys,
+            possession,
+            encryption: None,
+        }
     }
 
     pub fn identity(&self) -> Identity {
         self.identity
     }
+
+    pub fn possession(&self) -> &MultiSignature {
+        &self.possession
+    }
+
+    /// Returns the static exchange `PublicKey` of this `KeyCard`, if any.
+    ///
+    /// Every `KeyCard` obtained from a `KeyChain` carries an encryption key,
+    /// which can be used to `seal` messages to its holder. `KeyCard`s built
+    /// through `from_public_keys` carry none.
+    pub fn encryption_key(&self) -> Option<ExchangePublicKey> {
+        self.encryption.as_ref().map(|encryption| encryption.key)
+    }
+
+    /// Verifies that this `KeyCard`'s proof of possession was produced
+    /// by the holder of its multi-signature secret key.
+    ///
+    /// Every `KeyCard` obtained through `from_public_keys` or deserialization
+    /// has already passed this check: this prevents rogue-key attacks when
+    /// `KeyCard`s are used to verify aggregate `MultiSignature`s.
+    pub fn verify_possession(&self) -> Result<(), Top<KeyCardError>> {
+        self.possession
+            .verify_possession(&self.keys.multi)
+            .pot(KeyCardError::InvalidPossession, here!())
+    }
+
+    fn verify_encryption_key(&self) -> Result<(), Top<KeyCardError>> {
+        match &self.encryption {
+            Some(encryption) => encryption
+                .signature
+                .verify(self, &Encryption(encryption.key))
+                .pot(KeyCardError::InvalidEncryptionKey, here!()),
+            None => Ok(()),
+        }
+    }
+
+    /// Verifies that `output` is the result of evaluating the verifiable
+    /// random function of this `KeyCard`'s `KeyChain` on `message`.
+    ///
+    /// # Errors
+    ///
+    /// If `proof` is invalid, an `InvalidProof` error variant will be
+    /// returned. If `proof` is valid but does not prove `output`,
+    /// `MismatchedOutput` will be returned.
+    pub fn verify_vrf<S>(
+        &self,
+        message: &S,
+        output: &VrfOutput,
+        proof: &VrfProof,
+    ) -> Result<(), Top<VrfError>>
+    where
+        S: Statement,
+    {
+        let proven = proof.verify_raw(&self.keys.multi, &(S::SCOPE, S::HEADER, message))?;
+
+        if proven == *output {
+            Ok(())
+        } else {
+            VrfError::MismatchedOutput.fail().spot(here!())
+        }
+    }
+}
+
+impl PublicKeys {
+    fn identity(&self) -> Identity {
+        Identity::from_hash(hash::hash(self).unwrap())
+    }
+}
+
+impl KeyChain {
+    /// Returns the `Identity` of this `KeyChain`'s `KeyCard`.
+    pub fn identity(&self) -> Identity {
+        self.keycard.identity()
+    }
 }
 
 impl SignSignature {
@@ -102,6 +239,60 @@ impl MultiSignature {
             &(S::SCOPE, S::HEADER, message),
         )
     }
+
+    pub fn aggregate_verify<'a, E, R, S>(&self, entries: E) -> Result<(), Top<MultiError>>
+    where
+        E: IntoIterator<Item = (&'a R, &'a S)>,
+        R: 'a + MultiSigner,
+        S: 'a + Statement,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|(signer, message)| (signer.public_key(), (S::SCOPE, S::HEADER, message)))
+            .collect::<Vec<_>>();
+
+        self.aggregate_verify_raw(
+            entries
+                .iter()
+                .map(|(public_key, message)| (*public_key, message)),
+        )
+    }
+
+    pub fn batch_verify<'a, RI, R, MI, M, SI>(
+        signers: RI,
+        messages: MI,
+        signatures: SI,
+    ) -> Result<(), Top<MultiError>>
+    where
+        RI: IntoIterator<Item = &'a R>,
+        R: 'a + MultiSigner,
+        MI: IntoIterator<Item = &'a M>,
+        M: 'a + Statement,
+        SI: IntoIterator<Item = &'a MultiSignature>,
+    {
+        let public_keys = signers
+            .into_iter()
+            .map(MultiSigner::public_key)
+            .copied()
+            .collect::<Vec<_>>();
+
+        let messages = messages
+            .into_iter()
+            .map(|message| (M::SCOPE, M::HEADER, message))
+            .collect::<Vec<_>>();
+
+        let messages = messages.iter();
+
+        let signatures = signatures.into_iter().copied();
+
+        MultiSignature::batch_verify_raw(public_keys, messages, signatures)
+    }
+}
+
+impl Statement for Encryption {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::KeyCardEncryption;
 }
 
 impl SignSigner for KeyCard {
@@ -116,6 +307,12 @@ impl MultiSigner for KeyCard {
     }
 }
 
+impl From<&KeyCard> for Identity {
+    fn from(keycard: &KeyCard) -> Self {
+        keycard.identity()
+    }
+}
+
 impl PartialEq for KeyCard {
     fn eq(&self, rho: &KeyCard) -> bool {
         self.identity == rho.identity
@@ -150,7 +347,7 @@ impl Serialize for KeyCard {
     where
         S: Serializer,
     {
-        self.keys.serialize(serializer)
+        (&self.keys, &self.possession, &self.encryption).serialize(serializer)
     }
 }
 
@@ -159,7 +356,178 @@ impl<'de> Deserialize<'de> for KeyCard {
     where
         D: Deserializer<'de>,
     {
-        let keys = PublicKeys::deserialize(deserializer)?;
-        Ok(KeyCard::from_public_keys(keys.sign, keys.multi))
+        use serde::de::Error;
+
+        let (keys, possession, encryption) =
+            <(PublicKeys, MultiSignature, Option<EncryptionKey>)>::deserialize(deserializer)?;
+
+        let mut keycard = KeyCard::from_public_keys(keys.sign, keys.multi, possession)
+            .map_err(D::Error::custom)?;
+
+        keycard.encryption = encryption;
+        keycard.verify_encryption_key().map_err(D::Error::custom)?;
+
+        Ok(keycard)
+    }
+}
+
+impl Display for KeyCard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let bytes = bincode::serialize(self).unwrap();
+        f.write_str(&text::encode(TEXT_PREFIX, &bytes))
+    }
+}
+
+impl FromStr for KeyCard {
+    type Err = Top<TextError>;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = text::decode(TEXT_PREFIX, text)?;
+
+        // Deserialization verifies the proof of possession and the encryption key
+        bincode::deserialize(&bytes)
+            .map_err(|_| TextError::MalformedPayload.into_top())
+            .spot(here!())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct TestStatement;
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    #[test]
+    fn possession_correct() {
+        let keychain = KeyChain::random();
+        let keycard = keychain.keycard();
+
+        keycard.verify_possession().unwrap();
+        assert_eq!(keychain.identity(), keycard.identity());
+
+        let serialized = bincode::serialize(&keycard).unwrap();
+        let deserialized = bincode::deserialize::<KeyCard>(&serialized).unwrap();
+
+        assert_eq!(deserialized, keycard);
+    }
+
+    #[test]
+    fn possession_compromise() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let alice_keycard = alice.keycard();
+
+        assert!(KeyCard::from_public_keys(
+            *SignSigner::public_key(&alice_keycard),
+            *MultiSigner::public_key(&alice_keycard),
+            bob.prove_possession(),
+        )
+        .is_err());
+
+        let forged = (
+            &alice_keycard.keys,
+            bob.prove_possession(),
+            &alice_keycard.encryption,
+        );
+
+        let serialized = bincode::serialize(&forged).unwrap();
+
+        assert!(bincode::deserialize::<KeyCard>(&serialized).is_err());
+    }
+
+    #[test]
+    fn encryption_key_compromise() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let alice_keycard = alice.keycard();
+        let bob_keycard = bob.keycard();
+
+        assert!(alice_keycard.encryption_key().is_some());
+
+        let serialized = bincode::serialize(&alice_keycard).unwrap();
+        let deserialized = bincode::deserialize::<KeyCard>(&serialized).unwrap();
+
+        assert_eq!(
+            deserialized.encryption_key(),
+            alice_keycard.encryption_key()
+        );
+
+        let forged = (
+            &alice_keycard.keys,
+            &alice_keycard.possession,
+            &bob_keycard.encryption,
+        );
+
+        let serialized = bincode::serialize(&forged).unwrap();
+
+        assert!(bincode::deserialize::<KeyCard>(&serialized).is_err());
+    }
+
+    #[test]
+    fn vrf() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let (output, proof) = alice.evaluate_vrf(&TestStatement).unwrap();
+
+        alice
+            .keycard()
+            .verify_vrf(&TestStatement, &output, &proof)
+            .unwrap();
+
+        assert!(bob
+            .keycard()
+            .verify_vrf(&TestStatement, &output, &proof)
+            .is_err());
+
+        let (other_output, _) = bob.evaluate_vrf(&TestStatement).unwrap();
+
+        assert!(alice
+            .keycard()
+            .verify_vrf(&TestStatement, &other_output, &proof)
+            .is_err());
+    }
+
+    #[derive(Serialize)]
+    struct Vote(u64);
+
+    impl Statement for Vote {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    #[test]
+    fn aggregate_verify() {
+        let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let keycards = keychains.iter().map(KeyChain::keycard).collect::<Vec<_>>();
+        let votes = (0..4).map(Vote).collect::<Vec<_>>();
+
+        let signatures = keychains
+            .iter()
+            .zip(votes.iter())
+            .map(|(keychain, vote)| keychain.multisign(vote).unwrap())
+            .collect::<Vec<_>>();
+
+        MultiSignature::aggregate(signatures.iter().copied())
+            .unwrap()
+            .aggregate_verify(keycards.iter().zip(votes.iter()))
+            .unwrap();
+
+        MultiSignature::batch_verify(keycards.iter(), votes.iter(), signatures.iter()).unwrap();
+
+        assert!(MultiSignature::batch_verify(
+            keycards.iter(),
+            votes.iter().rev(),
+            signatures.iter()
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,25 @@
+use crate::crypto::{Committee, Identity, KeyCard};
+use std::collections::{BTreeMap, HashMap};
+
+/// A source of `KeyCard`s, indexed by `Identity`.
+pub trait KeyCardDirectory {
+    fn keycard(&self, identity: Identity) -> Option<&KeyCard>;
+}
+
+impl KeyCardDirectory for HashMap<Identity, KeyCard> {
+    fn keycard(&self, identity: Identity) -> Option<&KeyCard> {
+        self.get(&identity)
+    }
+}
+
+impl KeyCardDirectory for BTreeMap<Identity, KeyCard> {
+    fn keycard(&self, identity: Identity) -> Option<&KeyCard> {
+        self.get(&identity)
+    }
+}
+
+impl KeyCardDirectory for Committee {
+    fn keycard(&self, identity: Identity) -> Option<&KeyCard> {
+        self.index_of(identity).and_then(|index| self.get(index))
+    }
+}
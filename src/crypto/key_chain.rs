@@ -1,18 +1,37 @@
 use crate::crypto::{
     primitives::{
+        exchange::StaticKeyPair as ExchangeKeyPair,
         multi::{KeyPair as MultiKeyPair, MultiError, Signature as MultiSignature},
         sign::{KeyPair as SignKeyPair, SignError, Signature as SignSignature},
+        vrf::{Output as VrfOutput, Proof as VrfProof, VrfError},
     },
     KeyCard, Statement,
 };
+use blake3::Hasher;
 use doomstack::Top;
 use rand::{CryptoRng, RngCore};
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+use zeroize::Zeroize;
+
+const SEED_LENGTH: usize = 32;
+
+// `blake3` key derivation contexts, used for domain separation
+const SIGN_CONTEXT: &str = "talk 2022-06-01 KeyChain::from_seed sign";
+const MULTI_CONTEXT: &str = "talk 2022-06-01 KeyChain::from_seed multi";
+const DERIVE_CONTEXT: &str = "talk 2022-06-01 KeyChain::derive";
+const EXCHANGE_CONTEXT: &str = "talk 2022-06-01 KeyChain::exchange_keypair";
 
-#[derive(Clone, Serialize, Deserialize)]
+// The `KeyCard` of a `KeyChain` is built once, upon creation: building
+// it takes multiple signatures and a scalar multiplication
+#[derive(Clone)]
 pub struct KeyChain {
     pub(in crate::crypto) keypairs: Arc<KeyPairs>,
+    keycard: Arc<KeyCard>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -21,39 +40,273 @@ pub(in crate::crypto) struct KeyPairs {
     pub(in crate::crypto) multi: MultiKeyPair,
 }
 
+// `KeyChain`s are serialized as if `keycard` was not there
+#[derive(Serialize)]
+#[serde(rename = "KeyChain")]
+struct KeyChainRef<'a> {
+    keypairs: &'a KeyPairs,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "KeyChain")]
+struct KeyChainOwned {
+    keypairs: KeyPairs,
+}
+
 impl KeyChain {
     pub fn random() -> Self {
-        let keypairs = Arc::new(KeyPairs {
+        KeyChain::from_keypairs(KeyPairs {
             sign: SignKeyPair::random(),
             multi: MultiKeyPair::random(),
-        });
-
-        KeyChain { keypairs }
+        })
     }
 
     pub fn from_rng<R>(rng: &mut R) -> Self
     where
         R: CryptoRng + RngCore,
     {
-        let keypairs = Arc::new(KeyPairs {
+        KeyChain::from_keypairs(KeyPairs {
             sign: SignKeyPair::from_rng(rng),
             multi: MultiKeyPair::from_rng(rng),
-        });
+        })
+    }
+
+    /// Deterministically derives a `KeyChain` from a 32-byte `seed`.
+    ///
+    /// The `sign` and `multi` keypairs are derived from independent,
+    /// domain-separated hashes of `seed`. The same `seed` always yields
+    /// the same `KeyChain`: `seed` must be uniformly random and kept secret.
+    pub fn from_seed(seed: &[u8; SEED_LENGTH]) -> Self {
+        let mut sign_seed = blake3::derive_key(SIGN_CONTEXT, seed);
+        let mut multi_seed = blake3::derive_key(MULTI_CONTEXT, seed);
+
+        let keypairs = KeyPairs {
+            sign: SignKeyPair::from_seed(&sign_seed),
+            multi: MultiKeyPair::from_seed(&multi_seed),
+        };
+
+        sign_seed.zeroize();
+        multi_seed.zeroize();
+
+        KeyChain::from_keypairs(keypairs)
+    }
+
+    fn from_keypairs(keypairs: KeyPairs) -> Self {
+        let keycard = Arc::new(KeyCard::from_keypairs(&keypairs));
+
+        KeyChain {
+            keypairs: Arc::new(keypairs),
+            keycard,
+        }
+    }
+
+    /// Deterministically derives a child `KeyChain` from this `KeyChain`'s
+    /// secret keys and a `/`-separated `path` (e.g., `"consensus/epoch-3"`).
+    ///
+    /// Derivation is hierarchical: each segment of `path` derives a child of
+    /// the `KeyChain` derived by the previous segment, so that
+    /// `derive("a/b")` is the same as `derive("a").derive("b")`. Empty
+    /// segments are ignored. Knowledge of a child `KeyChain` reveals nothing
+    /// about its parent or its siblings.
+    pub fn derive(&self, path: &str) -> KeyChain {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .fold(self.clone(), |parent, segment| parent.derive_child(segment))
+    }
+
+    fn derive_child(&self, segment: &str) -> KeyChain {
+        let mut hasher = Hasher::new_derive_key(DERIVE_CONTEXT);
 
-        KeyChain { keypairs }
+        let mut sign = self.keypairs.sign.to_bytes();
+        let mut multi = self.keypairs.multi.to_bytes();
+
+        hasher.update(&sign);
+        hasher.update(&multi);
+        hasher.update(&(segment.len() as u64).to_le_bytes());
+        hasher.update(segment.as_bytes());
+
+        sign.zeroize();
+        multi.zeroize();
+
+        let mut seed = *hasher.finalize().as_bytes();
+        hasher.zeroize();
+
+        let child = KeyChain::from_seed(&seed);
+        seed.zeroize();
+
+        child
     }
 
     pub fn keycard(&self) -> KeyCard {
-        KeyCard::from_keychain(&self)
+        self.keycard.as_ref().clone()
+    }
+
+    /// Produces a proof that this `KeyChain` knows the secret key
+    /// of its multi-signature `KeyPair`, as carried by its `KeyCard`.
+    pub fn prove_possession(&self) -> MultiSignature {
+        self.keypairs.prove_possession()
+    }
+
+    pub(in crate::crypto) fn exchange_keypair(&self) -> ExchangeKeyPair {
+        self.keypairs.exchange_keypair()
     }
 
     pub fn sign<S: Statement>(&self, message: &S) -> Result<SignSignature, Top<SignError>> {
-        self.keypairs.sign.sign_raw(&(S::SCOPE, S::HEADER, message))
+        self.keypairs.sign(message)
     }
 
     pub fn multisign<S: Statement>(&self, message: &S) -> Result<MultiSignature, Top<MultiError>> {
-        self.keypairs
-            .multi
-            .sign_raw(&(S::SCOPE, S::HEADER, message))
+        self.keypairs.multisign(message)
+    }
+
+    /// Evaluates this `KeyChain`'s verifiable random function on `message`.
+    ///
+    /// The `VrfOutput` is uniquely determined by this `KeyChain` and `message`,
+    /// and is unpredictable to anyone who does not hold this `KeyChain`. Along
+    /// with the `VrfProof`, it can be checked using `KeyCard::verify_vrf`.
+    pub fn evaluate_vrf<S: Statement>(
+        &self,
+        message: &S,
+    ) -> Result<(VrfOutput, VrfProof), Top<VrfError>> {
+        VrfProof::evaluate_raw(&self.keypairs.multi, &(S::SCOPE, S::HEADER, message))
+    }
+}
+
+impl KeyPairs {
+    pub(in crate::crypto) fn prove_possession(&self) -> MultiSignature {
+        self.multi.prove_possession()
+    }
+
+    // The static exchange `KeyPair` is derived from the secret key of the
+    // `sign` `KeyPair`, so that `KeyChain`s keep their serialized format
+    pub(in crate::crypto) fn exchange_keypair(&self) -> ExchangeKeyPair {
+        let mut sign = self.sign.to_bytes();
+        let mut seed = blake3::derive_key(EXCHANGE_CONTEXT, &sign);
+
+        let keypair = ExchangeKeyPair::from_seed(&seed);
+
+        sign.zeroize();
+        seed.zeroize();
+
+        keypair
+    }
+
+    pub(in crate::crypto) fn sign<S: Statement>(
+        &self,
+        message: &S,
+    ) -> Result<SignSignature, Top<SignError>> {
+        self.sign.sign_raw(&(S::SCOPE, S::HEADER, message))
+    }
+
+    pub(in crate::crypto) fn multisign<S: Statement>(
+        &self,
+        message: &S,
+    ) -> Result<MultiSignature, Top<MultiError>> {
+        self.multi.sign_raw(&(S::SCOPE, S::HEADER, message))
+    }
+}
+
+impl Serialize for KeyChain {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        KeyChainRef {
+            keypairs: self.keypairs.as_ref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyChain {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let KeyChainOwned { keypairs } = KeyChainOwned::deserialize(deserializer)?;
+        Ok(KeyChain::from_keypairs(keypairs))
+    }
+}
+
+impl Debug for KeyChain {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Only public keys are printed: secret keys never leave the `KeyChain`
+        f.debug_struct("KeyChain")
+            .field("sign", &self.keypairs.sign.public())
+            .field("multi", &self.keypairs.multi.public())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_seed_deterministic() {
+        let alice = KeyChain::from_seed(&[0u8; SEED_LENGTH]);
+        let also_alice = KeyChain::from_seed(&[0u8; SEED_LENGTH]);
+        let bob = KeyChain::from_seed(&[1u8; SEED_LENGTH]);
+
+        assert_eq!(alice.keycard(), also_alice.keycard());
+        assert_ne!(alice.keycard(), bob.keycard());
+    }
+
+    #[test]
+    fn derive_deterministic() {
+        let root = KeyChain::from_seed(&[0u8; SEED_LENGTH]);
+
+        let consensus = root.derive("consensus");
+        let broadcast = root.derive("broadcast");
+
+        assert_eq!(consensus.keycard(), root.derive("consensus").keycard());
+        assert_ne!(consensus.keycard(), broadcast.keycard());
+        assert_ne!(consensus.keycard(), root.keycard());
+    }
+
+    #[test]
+    fn derive_hierarchical() {
+        let root = KeyChain::random();
+
+        assert_eq!(
+            root.derive("consensus/epoch-3").keycard(),
+            root.derive("consensus").derive("epoch-3").keycard()
+        );
+
+        assert_eq!(
+            root.derive("/consensus//epoch-3/").keycard(),
+            root.derive("consensus/epoch-3").keycard()
+        );
+
+        assert_eq!(root.derive("").keycard(), root.keycard());
+    }
+
+    #[test]
+    fn serde_keycard() {
+        let keychain = KeyChain::random();
+
+        let bytes = bincode::serialize(&keychain).unwrap();
+        let also_keychain = bincode::deserialize::<KeyChain>(&bytes).unwrap();
+
+        assert_eq!(also_keychain.keycard(), keychain.keycard());
+        assert_eq!(
+            also_keychain.keycard(),
+            KeyCard::from_keypairs(&keychain.keypairs)
+        );
+    }
+
+    #[test]
+    fn debug_redacted() {
+        let keychain = KeyChain::random();
+
+        let debug = format!("{:?}", keychain);
+
+        let secret = keychain.keypairs.sign.to_bytes()[..32]
+            .iter()
+            .map(|byte| format!("{:02x?}", byte))
+            .collect::<String>();
+
+        assert!(debug.starts_with("KeyChain"));
+        assert!(!debug.contains(&secret));
     }
 }
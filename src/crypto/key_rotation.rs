@@ -0,0 +1,141 @@
+use crate::crypto::{
+    primitives::sign::Signature as SignSignature, Identity, KeyCard, KeyChain, LineageError, Scope,
+    Statement, TalkHeader,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Serialize};
+
+/// Statement by which the holder of `previous` designates `next`
+/// as its successor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotation {
+    previous: Identity,
+    next: KeyCard,
+}
+
+/// A `KeyRotation`, signed by the `KeyChain` being rotated out and
+/// countersigned by its successor.
+///
+/// The countersignature prevents a node from designating someone else's
+/// `KeyCard` as its successor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Succession {
+    rotation: KeyRotation,
+    signature: SignSignature,
+    countersignature: SignSignature,
+}
+
+// Signed by the successor designated by a `KeyRotation`
+#[derive(Serialize)]
+struct Acceptance<'r>(&'r KeyRotation);
+
+impl KeyRotation {
+    pub fn previous(&self) -> Identity {
+        self.previous
+    }
+
+    pub fn next(&self) -> &KeyCard {
+        &self.next
+    }
+}
+
+impl Succession {
+    pub fn previous(&self) -> Identity {
+        self.rotation.previous
+    }
+
+    pub fn next(&self) -> &KeyCard {
+        &self.rotation.next
+    }
+
+    pub fn rotation(&self) -> &KeyRotation {
+        &self.rotation
+    }
+
+    /// Verifies that this `Succession` was signed by `previous`, and
+    /// countersigned by `next`.
+    ///
+    /// # Errors
+    ///
+    /// If `previous` is not the `KeyCard` being rotated out, a
+    /// `MismatchedPrevious` error variant will be returned. If either
+    /// signature is invalid, `InvalidSuccession` will be returned.
+    pub fn verify(&self, previous: &KeyCard) -> Result<(), Top<LineageError>> {
+        if previous.identity() != self.rotation.previous {
+            return LineageError::MismatchedPrevious.fail().spot(here!());
+        }
+
+        self.signature
+            .verify(previous, &self.rotation)
+            .pot(LineageError::InvalidSuccession, here!())?;
+
+        self.countersignature
+            .verify(&self.rotation.next, &Acceptance(&self.rotation))
+            .pot(LineageError::InvalidSuccession, here!())
+    }
+}
+
+impl KeyChain {
+    /// Designates `next` as the successor of this `KeyChain`.
+    ///
+    /// Both `KeyChain`s sign the resulting `Succession`: only the holder
+    /// of `next` can accept to succeed this `KeyChain`.
+    pub fn rotate(&self, next: &KeyChain) -> Succession {
+        let rotation = KeyRotation {
+            previous: self.keycard().identity(),
+            next: next.keycard(),
+        };
+
+        let signature = self.sign(&rotation).unwrap();
+        let countersignature = next.sign(&Acceptance(&rotation)).unwrap();
+
+        Succession {
+            rotation,
+            signature,
+            countersignature,
+        }
+    }
+}
+
+impl Statement for KeyRotation {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::KeyRotation;
+}
+
+impl Statement for Acceptance<'_> {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::KeyRotationAcceptance;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compromise_countersignature() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+        let eve = KeyChain::random();
+
+        alice.rotate(&bob).verify(&alice.keycard()).unwrap();
+
+        // `alice` cannot designate `bob` as her successor without his consent
+        let rotation = KeyRotation {
+            previous: alice.keycard().identity(),
+            next: bob.keycard(),
+        };
+
+        let signature = alice.sign(&rotation).unwrap();
+        let countersignature = eve.sign(&Acceptance(&rotation)).unwrap();
+
+        let succession = Succession {
+            rotation,
+            signature,
+            countersignature,
+        };
+
+        assert!(succession.verify(&alice.keycard()).is_err());
+    }
+}
@@ -0,0 +1,462 @@
+use crate::crypto::KeyChain;
+use argon2::{Algorithm, Argon2, Params, Version};
+use bincode::Options;
+use chacha20poly1305::{
+    aead::{Aead as ChaChaAead, NewAead as ChaChaNewAead, Payload as ChaChaPayload},
+    ChaCha20Poly1305, Key as ChaChaKey, Nonce as ChaChaNonce,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs,
+    fs::OpenOptions,
+    io,
+    io::{Read, Write},
+    path::Path,
+};
+use zeroize::Zeroize;
+
+const MAGIC: &[u8; 8] = b"TALKKEYS";
+const VERSION: u8 = 1;
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const KEY_LENGTH: usize = 32;
+
+// Argon2id parameters, as recommended by OWASP (19 MiB of memory, 2 passes)
+const MEMORY_COST: u32 = 19 * 1024;
+const TIME_COST: u32 = 2;
+const PARALLELISM: u32 = 1;
+
+// Argon2id parameters are read from the file before it is authenticated:
+// parameters exceeding these bounds are rejected, so that a tampered file
+// cannot make `load_encrypted` exhaust memory or time
+const MAX_MEMORY_COST: u32 = 4 * MEMORY_COST;
+const MAX_TIME_COST: u32 = 4 * TIME_COST;
+const MAX_PARALLELISM: u32 = 4 * PARALLELISM;
+
+// Keystores are a few hundred bytes long: anything much larger is malformed
+const MAX_KEYSTORE_SIZE: u64 = 1 << 16; // 64 KiB
+
+#[derive(Doom)]
+pub enum KeyStoreError {
+    #[doom(description("Failed to decrypt keystore (wrong passphrase or corrupted file)"))]
+    DecryptFailed,
+    #[doom(description("Failed to deserialize: {}", source))]
+    #[doom(wrap(deserialize_failed))]
+    DeserializeFailed { source: bincode::Error },
+    #[doom(description("Key derivation parameters exceed the allowed maximum"))]
+    ExcessiveKdfCost,
+    #[doom(description("Failed to derive key from passphrase"))]
+    KdfFailed,
+    #[doom(description("Malformed keystore"))]
+    MalformedKeyStore,
+    #[doom(description("Failed to read: {}", source))]
+    #[doom(wrap(read_failed))]
+    ReadFailed { source: io::Error },
+    #[doom(description("Failed to serialize: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+    #[doom(description("Unsupported keystore version"))]
+    UnsupportedVersion,
+    #[doom(description("Failed to write: {}", source))]
+    #[doom(wrap(write_failed))]
+    WriteFailed { source: io::Error },
+}
+
+// On disk, a keystore is laid out as `MAGIC || version || body`, where the
+// layout of `body` depends on `version`. Version 1 `body`s are `bincode`-
+// serialized `KeyStoreV1`s.
+#[derive(Serialize, Deserialize)]
+struct KeyStoreV1 {
+    header: HeaderV1,
+    ciphertext: Vec<u8>,
+}
+
+// `HeaderV1` is authenticated as associated data alongside the ciphertext
+#[derive(Serialize, Deserialize)]
+struct HeaderV1 {
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+    salt: [u8; SALT_LENGTH],
+    nonce: [u8; NONCE_LENGTH],
+}
+
+impl KeyChain {
+    /// Saves this `KeyChain` to `path`, encrypted under `passphrase`.
+    ///
+    /// The encryption key is derived from `passphrase` using Argon2id, and
+    /// the `KeyChain` is encrypted using ChaCha20-Poly1305. If a file already
+    /// exists at `path`, it is atomically replaced: the keystore is first
+    /// written to a temporary file in the same directory, which is then
+    /// renamed to `path`. If saving fails, any existing file is left intact.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `path` fails, a `WriteFailed` error variant will be returned.
+    pub fn save_encrypted<P>(&self, path: P, passphrase: &str) -> Result<(), Top<KeyStoreError>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let header = HeaderV1 {
+            memory_cost: MEMORY_COST,
+            time_cost: TIME_COST,
+            parallelism: PARALLELISM,
+            salt,
+            nonce,
+        };
+
+        let cipher = header.cipher(passphrase)?;
+
+        let associated_data = bincode::serialize(&header)
+            .map_err(KeyStoreError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let mut plaintext = bincode::serialize(self)
+            .map_err(KeyStoreError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let ciphertext = cipher
+            .encrypt(
+                ChaChaNonce::from_slice(&header.nonce),
+                ChaChaPayload {
+                    msg: &plaintext,
+                    aad: &associated_data,
+                },
+            )
+            .unwrap(); // Encryption does not fail for buffers shorter than 256 GiB
+
+        plaintext.zeroize();
+
+        let body = bincode::serialize(&KeyStoreV1 { header, ciphertext })
+            .map_err(KeyStoreError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        write_private(path.as_ref(), &[MAGIC, &[VERSION], &body])
+            .map_err(KeyStoreError::write_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())
+    }
+
+    /// Loads a `KeyChain` from `path`, as saved by `save_encrypted`.
+    ///
+    /// # Errors
+    ///
+    /// If `passphrase` is incorrect, or the file at `path` was tampered with,
+    /// a `DecryptFailed` error variant will be returned. If the file was saved
+    /// by a newer version of the keystore format, `UnsupportedVersion` will be
+    /// returned.
+    pub fn load_encrypted<P>(path: P, passphrase: &str) -> Result<KeyChain, Top<KeyStoreError>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut bytes = Vec::new();
+
+        fs::File::open(path)
+            .and_then(|file| file.take(MAX_KEYSTORE_SIZE + 1).read_to_end(&mut bytes))
+            .map_err(KeyStoreError::read_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        if bytes.len() as u64 > MAX_KEYSTORE_SIZE {
+            return KeyStoreError::MalformedKeyStore.fail().spot(here!());
+        }
+
+        if bytes.len() < MAGIC.len() + 1 || &bytes[..MAGIC.len()] != MAGIC {
+            return KeyStoreError::MalformedKeyStore.fail().spot(here!());
+        }
+
+        let version = bytes[MAGIC.len()];
+        let body = &bytes[MAGIC.len() + 1..];
+
+        match version {
+            1 => load_v1(body, passphrase),
+            _ => KeyStoreError::UnsupportedVersion.fail().spot(here!()),
+        }
+    }
+}
+
+impl HeaderV1 {
+    fn cipher(&self, passphrase: &str) -> Result<ChaCha20Poly1305, Top<KeyStoreError>> {
+        if self.memory_cost > MAX_MEMORY_COST
+            || self.time_cost > MAX_TIME_COST
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return KeyStoreError::ExcessiveKdfCost.fail().spot(here!());
+        }
+
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(|_| KeyStoreError::KdfFailed.into_top())
+        .spot(here!())?;
+
+        let mut key = [0u8; KEY_LENGTH];
+
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)
+            .map_err(|_| KeyStoreError::KdfFailed.into_top())
+            .spot(here!())?;
+
+        let cipher = ChaCha20Poly1305::new(ChaChaKey::from_slice(&key));
+        key.zeroize();
+
+        Ok(cipher)
+    }
+}
+
+fn load_v1(body: &[u8], passphrase: &str) -> Result<KeyChain, Top<KeyStoreError>> {
+    let keystore: KeyStoreV1 = deserialize(body)?;
+
+    let cipher = keystore.header.cipher(passphrase)?;
+
+    let associated_data = bincode::serialize(&keystore.header)
+        .map_err(KeyStoreError::serialize_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
+
+    let mut plaintext = cipher
+        .decrypt(
+            ChaChaNonce::from_slice(&keystore.header.nonce),
+            ChaChaPayload {
+                msg: &keystore.ciphertext,
+                aad: &associated_data,
+            },
+        )
+        .map_err(|_| KeyStoreError::DecryptFailed.into_top())
+        .spot(here!())?;
+
+    let keychain = deserialize(&plaintext);
+
+    plaintext.zeroize();
+
+    keychain
+}
+
+// Equivalent to `bincode::deserialize`, with allocations bounded by `MAX_KEYSTORE_SIZE`
+fn deserialize<T>(bytes: &[u8]) -> Result<T, Top<KeyStoreError>>
+where
+    T: DeserializeOwned,
+{
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(MAX_KEYSTORE_SIZE)
+        .deserialize(bytes)
+        .map_err(KeyStoreError::deserialize_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())
+}
+
+// Writes `chunks` to a temporary file, readable only by its owner, then
+// renames it to `path`: a failure never leaves `path` partially written
+fn write_private(path: &Path, chunks: &[&[u8]]) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+
+    let mut temporary = name.to_os_string();
+    temporary.push(format!(".tmp-{:016x}", OsRng.next_u64()));
+
+    let temporary = path.with_file_name(temporary);
+
+    let result = (|| {
+        let mut file = create_private(&temporary)?;
+
+        for chunk in chunks {
+            file.write_all(chunk)?;
+        }
+
+        file.sync_all()?;
+        fs::rename(&temporary, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+
+    result?;
+
+    // Persist the rename
+    #[cfg(unix)]
+    {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        fs::File::open(directory)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+fn create_private(path: &Path) -> io::Result<fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    // Keystores should only be readable by their owner
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Statement;
+    use std::{env, process};
+
+    #[derive(Serialize)]
+    struct TestStatement;
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    fn temporary_path(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("talk-key-store-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn save_load() {
+        let path = temporary_path("save_load");
+
+        let keychain = KeyChain::random();
+        keychain.save_encrypted(&path, "hunter2").unwrap();
+
+        let loaded = KeyChain::load_encrypted(&path, "hunter2").unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.keycard(), keychain.keycard());
+
+        let signature = loaded.sign(&TestStatement).unwrap();
+        signature
+            .verify(&keychain.keycard(), &TestStatement)
+            .unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let path = temporary_path("wrong_passphrase");
+
+        KeyChain::random().save_encrypted(&path, "hunter2").unwrap();
+
+        let loaded = KeyChain::load_encrypted(&path, "hunter3");
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    fn compromise_ciphertext() {
+        let path = temporary_path("compromise_ciphertext");
+
+        KeyChain::random().save_encrypted(&path, "hunter2").unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] = bytes[last].wrapping_add(1);
+        fs::write(&path, &bytes).unwrap();
+
+        let loaded = KeyChain::load_encrypted(&path, "hunter2");
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    fn excessive_kdf_cost() {
+        let path = temporary_path("excessive_kdf_cost");
+
+        KeyChain::random().save_encrypted(&path, "hunter2").unwrap();
+
+        // `memory_cost` is the first field of the header
+        let mut bytes = fs::read(&path).unwrap();
+        let offset = MAGIC.len() + 1;
+        bytes[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+
+        let loaded = KeyChain::load_encrypted(&path, "hunter2");
+        fs::remove_file(&path).unwrap();
+
+        match loaded.unwrap_err().top() {
+            KeyStoreError::ExcessiveKdfCost => (),
+            error => panic!("unexpected error upon loading costly keystore: {}", error),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temporary_path("permissions");
+
+        fs::write(&path, b"").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        KeyChain::random().save_encrypted(&path, "hunter2").unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn overwrite() {
+        let directory = temporary_path("overwrite");
+        fs::create_dir_all(&directory).unwrap();
+
+        let path = directory.join("keystore");
+
+        KeyChain::random().save_encrypted(&path, "hunter2").unwrap();
+
+        let keychain = KeyChain::random();
+        keychain.save_encrypted(&path, "hunter3").unwrap();
+
+        let loaded = KeyChain::load_encrypted(&path, "hunter3").unwrap();
+        let entries = fs::read_dir(&directory).unwrap().count();
+
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(loaded.keycard(), keychain.keycard());
+        assert_eq!(entries, 1); // No temporary file is left behind
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let path = temporary_path("unsupported_version");
+
+        KeyChain::random().save_encrypted(&path, "hunter2").unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[MAGIC.len()] = VERSION + 1;
+        fs::write(&path, &bytes).unwrap();
+
+        let loaded = KeyChain::load_encrypted(&path, "hunter2");
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+}
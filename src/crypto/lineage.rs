@@ -0,0 +1,191 @@
+use crate::crypto::{Identity, KeyCard, Succession};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The history of `KeyCard`s held by a single node, starting from its
+/// `genesis` `KeyCard`, where every `KeyCard` was designated by its
+/// predecessor through a `Succession`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Lineage {
+    genesis: KeyCard,
+    successions: Vec<Succession>,
+}
+
+#[derive(Doom)]
+pub enum LineageError {
+    #[doom(description("Invalid succession signature"))]
+    InvalidSuccession,
+    #[doom(description("Succession does not rotate out the expected `KeyCard`"))]
+    MismatchedPrevious,
+    #[doom(description("Succession designates an identity already in the lineage"))]
+    RepeatedIdentity,
+}
+
+impl Lineage {
+    pub fn new(genesis: KeyCard) -> Self {
+        Lineage {
+            genesis,
+            successions: Vec::new(),
+        }
+    }
+
+    /// Builds a `Lineage` out of its `genesis` and its `successions`,
+    /// verifying every `Succession` in order.
+    pub fn from_successions<I>(genesis: KeyCard, successions: I) -> Result<Self, Top<LineageError>>
+    where
+        I: IntoIterator<Item = Succession>,
+    {
+        let mut lineage = Lineage::new(genesis);
+
+        for succession in successions {
+            lineage.extend(succession)?;
+        }
+
+        Ok(lineage)
+    }
+
+    /// Appends `succession` to this `Lineage`.
+    ///
+    /// # Errors
+    ///
+    /// If `succession` is not signed by the `current` `KeyCard`, a
+    /// `MismatchedPrevious` or `InvalidSuccession` error variant will be
+    /// returned. If `succession` designates a `KeyCard` that already appears
+    /// in this `Lineage`, `RepeatedIdentity` will be returned.
+    pub fn extend(&mut self, succession: Succession) -> Result<(), Top<LineageError>> {
+        succession.verify(self.current()).spot(here!())?;
+
+        if self.contains(succession.next().identity()) {
+            return LineageError::RepeatedIdentity.fail().spot(here!());
+        }
+
+        self.successions.push(succession);
+        Ok(())
+    }
+
+    pub fn genesis(&self) -> &KeyCard {
+        &self.genesis
+    }
+
+    /// Returns the latest `KeyCard` in this `Lineage`.
+    pub fn current(&self) -> &KeyCard {
+        self.successions
+            .last()
+            .map(Succession::next)
+            .unwrap_or(&self.genesis)
+    }
+
+    pub fn successions(&self) -> &[Succession] {
+        self.successions.as_slice()
+    }
+
+    /// Iterates over the `Identity`s in this `Lineage`, oldest first.
+    pub fn identities(&self) -> impl Iterator<Item = Identity> + '_ {
+        Some(self.genesis.identity()).into_iter().chain(
+            self.successions
+                .iter()
+                .map(|succession| succession.next().identity()),
+        )
+    }
+
+    pub fn contains(&self, identity: Identity) -> bool {
+        self.identities().any(|historical| historical == identity)
+    }
+
+    /// Maps any `identity` in this `Lineage` to the `current` `KeyCard`.
+    pub fn resolve(&self, identity: Identity) -> Option<&KeyCard> {
+        if self.contains(identity) {
+            Some(self.current())
+        } else {
+            None
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Lineage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let (genesis, successions) = <(KeyCard, Vec<Succession>)>::deserialize(deserializer)?;
+        Lineage::from_successions(genesis, successions).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyChain;
+
+    #[test]
+    fn rotations() {
+        let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let mut lineage = Lineage::new(keychains[0].keycard());
+
+        for window in keychains.windows(2) {
+            lineage.extend(window[0].rotate(&window[1])).unwrap();
+        }
+
+        assert_eq!(lineage.current(), &keychains[3].keycard());
+
+        for keychain in keychains.iter() {
+            assert_eq!(
+                lineage.resolve(keychain.keycard().identity()),
+                Some(&keychains[3].keycard())
+            );
+        }
+
+        assert!(lineage
+            .resolve(KeyChain::random().keycard().identity())
+            .is_none());
+
+        let serialized = bincode::serialize(&lineage).unwrap();
+        let deserialized = bincode::deserialize::<Lineage>(&serialized).unwrap();
+
+        assert_eq!(
+            deserialized.identities().collect::<Vec<_>>(),
+            lineage.identities().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn compromise_predecessor() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+        let eve = KeyChain::random();
+
+        let mut lineage = Lineage::new(alice.keycard());
+
+        // `eve` cannot rotate `alice` out
+        assert!(lineage.extend(eve.rotate(&bob)).is_err());
+
+        // `alice` cannot be rotated out twice
+        lineage.extend(alice.rotate(&bob)).unwrap();
+        assert!(lineage.extend(alice.rotate(&eve)).is_err());
+    }
+
+    #[test]
+    fn compromise_cycle() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let mut lineage = Lineage::new(alice.keycard());
+        lineage.extend(alice.rotate(&bob)).unwrap();
+
+        assert!(lineage.extend(bob.rotate(&alice)).is_err());
+    }
+
+    #[test]
+    fn compromise_serialized() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+        let eve = KeyChain::random();
+
+        let forged = (alice.keycard(), vec![eve.rotate(&bob)]);
+        let serialized = bincode::serialize(&forged).unwrap();
+
+        assert!(bincode::deserialize::<Lineage>(&serialized).is_err());
+    }
+}
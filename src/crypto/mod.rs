@@ -1,8 +1,18 @@
+mod certificate;
+mod committee;
 mod identity;
 mod key_card;
+mod key_card_directory;
 mod key_chain;
+mod key_rotation;
+mod key_store;
+mod lineage;
+mod peer_record;
 mod scope;
+mod sealed_box;
+mod signed;
 mod statement;
+mod statement_registry;
 mod talk_header;
 
 pub mod primitives;
@@ -10,8 +20,19 @@ pub mod procedures;
 
 pub(crate) use talk_header::TalkHeader;
 
+pub use certificate::{Certificate, CertificateError};
+pub use committee::Committee;
 pub use identity::Identity;
-pub use key_card::KeyCard;
+pub use key_card::{KeyCard, KeyCardError};
+pub use key_card_directory::KeyCardDirectory;
 pub use key_chain::KeyChain;
+pub use key_rotation::{KeyRotation, Succession};
+pub use key_store::KeyStoreError;
+pub use lineage::{Lineage, LineageError};
+pub use peer_record::{PeerRecord, PeerRecordError};
 pub use scope::Scope;
-pub use statement::Statement;
+pub use sealed_box::SealedBoxError;
+pub use signed::{Signed, SignedError};
+pub use statement::{HeaderClaim, Statement};
+pub use statement_registry::{StatementRegistry, StatementRegistryError};
+pub use talk_derive::Statement;
@@ -0,0 +1,245 @@
+use crate::crypto::{
+    primitives::sign::Signature as SignSignature, Identity, KeyCard, KeyChain, Scope, Statement,
+    TalkHeader,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeMap, net::SocketAddr, time::SystemTime};
+
+/// Metadata published by a node about itself: its `KeyCard`, the
+/// addresses at which it can be reached and free-form attributes.
+///
+/// A `PeerRecord` is signed by the `KeyChain` of its `KeyCard`, so that
+/// it can be relayed by untrusted directories or gossip layers. Newer
+/// `PeerRecord`s carry a higher `sequence` number, and every `PeerRecord`
+/// stops being valid at its `expiry`.
+#[derive(Debug, Clone)]
+pub struct PeerRecord {
+    contents: Contents,
+    signature: SignSignature,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Contents {
+    keycard: KeyCard,
+    sequence: u64,
+    addresses: Vec<SocketAddr>,
+    attributes: BTreeMap<String, String>,
+    expiry: SystemTime,
+}
+
+#[derive(Doom)]
+pub enum PeerRecordError {
+    #[doom(description("Peer record is expired"))]
+    Expired,
+    #[doom(description("Invalid peer record signature"))]
+    InvalidSignature,
+}
+
+impl PeerRecord {
+    /// Builds a `PeerRecord` for `keychain`, signed by `keychain`.
+    pub fn new(
+        keychain: &KeyChain,
+        sequence: u64,
+        addresses: Vec<SocketAddr>,
+        attributes: BTreeMap<String, String>,
+        expiry: SystemTime,
+    ) -> Self {
+        let contents = Contents {
+            keycard: keychain.keycard(),
+            sequence,
+            addresses,
+            attributes,
+            expiry,
+        };
+
+        let signature = keychain.sign(&contents).unwrap();
+
+        PeerRecord {
+            contents,
+            signature,
+        }
+    }
+
+    pub fn keycard(&self) -> &KeyCard {
+        &self.contents.keycard
+    }
+
+    pub fn identity(&self) -> Identity {
+        self.contents.keycard.identity()
+    }
+
+    pub fn sequence(&self) -> u64 {
+        self.contents.sequence
+    }
+
+    pub fn addresses(&self) -> &[SocketAddr] {
+        self.contents.addresses.as_slice()
+    }
+
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.contents.attributes
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.contents.attributes.get(key).map(String::as_str)
+    }
+
+    pub fn expiry(&self) -> SystemTime {
+        self.contents.expiry
+    }
+
+    pub fn is_expired(&self) -> bool {
+        SystemTime::now() >= self.contents.expiry
+    }
+
+    /// Returns `true` if this `PeerRecord` should replace `other`, i.e.,
+    /// if both belong to the same `Identity` and this `PeerRecord` has a
+    /// higher `sequence` number.
+    pub fn supersedes(&self, other: &PeerRecord) -> bool {
+        self.identity() == other.identity() && self.sequence() > other.sequence()
+    }
+
+    /// Verifies that this `PeerRecord` was signed by the holder of its `KeyCard`.
+    ///
+    /// Every `PeerRecord` obtained through deserialization has already
+    /// passed this check.
+    pub fn verify(&self) -> Result<(), Top<PeerRecordError>> {
+        self.signature
+            .verify(&self.contents.keycard, &self.contents)
+            .pot(PeerRecordError::InvalidSignature, here!())
+    }
+
+    /// Verifies this `PeerRecord`, and checks that it has not expired.
+    ///
+    /// # Errors
+    ///
+    /// If the signature is invalid, an `InvalidSignature` error variant
+    /// will be returned. If this `PeerRecord` has expired, `Expired` will
+    /// be returned.
+    pub fn validate(&self) -> Result<(), Top<PeerRecordError>> {
+        self.verify()?;
+
+        if self.is_expired() {
+            return PeerRecordError::Expired.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+}
+
+impl Statement for Contents {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::PeerRecord;
+}
+
+impl Serialize for PeerRecord {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (&self.contents, &self.signature).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PeerRecord {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let (contents, signature) = <(Contents, SignSignature)>::deserialize(deserializer)?;
+
+        let record = PeerRecord {
+            contents,
+            signature,
+        };
+
+        record.verify().map_err(D::Error::custom)?;
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record(keychain: &KeyChain, sequence: u64, lifetime: Duration) -> PeerRecord {
+        let mut attributes = BTreeMap::new();
+        attributes.insert("role".to_string(), "relay".to_string());
+
+        PeerRecord::new(
+            keychain,
+            sequence,
+            vec!["127.0.0.1:9000".parse().unwrap()],
+            attributes,
+            SystemTime::now() + lifetime,
+        )
+    }
+
+    #[test]
+    fn correct() {
+        let keychain = KeyChain::random();
+        let record = record(&keychain, 0, Duration::from_secs(3600));
+
+        record.validate().unwrap();
+
+        let bytes = bincode::serialize(&record).unwrap();
+        let record: PeerRecord = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(record.identity(), keychain.identity());
+        assert_eq!(
+            record.addresses(),
+            &["127.0.0.1:9000".parse::<SocketAddr>().unwrap()]
+        );
+        assert_eq!(record.attribute("role"), Some("relay"));
+
+        record.validate().unwrap();
+    }
+
+    #[test]
+    fn expired() {
+        let keychain = KeyChain::random();
+        let record = record(&keychain, 0, Duration::from_secs(0));
+
+        record.verify().unwrap();
+        assert!(record.validate().is_err());
+    }
+
+    #[test]
+    fn supersedes() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let old = record(&alice, 0, Duration::from_secs(3600));
+        let new = record(&alice, 1, Duration::from_secs(3600));
+        let other = record(&bob, 2, Duration::from_secs(3600));
+
+        assert!(new.supersedes(&old));
+        assert!(!old.supersedes(&new));
+        assert!(!other.supersedes(&old));
+    }
+
+    #[test]
+    fn compromise() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let mut record = record(&alice, 0, Duration::from_secs(3600));
+        record.contents.addresses = vec!["10.0.0.1:9000".parse().unwrap()];
+
+        assert!(record.verify().is_err());
+
+        let bytes = bincode::serialize(&record).unwrap();
+        assert!(bincode::deserialize::<PeerRecord>(&bytes).is_err());
+
+        record.contents.keycard = bob.keycard();
+        record.signature = alice.sign(&record.contents).unwrap();
+
+        assert!(record.verify().is_err());
+    }
+}
@@ -10,17 +10,39 @@ use chacha20poly1305::{
 use doomstack::{here, Doom, ResultExt, Top};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::convert::TryInto;
+use zeroize::Zeroize;
 
 const NONCE_LENGTH: usize = 12;
 
+// `blake3` key derivation context, used to ratchet keys
+const RATCHET_CONTEXT: &str = "talk 2022-06-01 channel::State::ratchet";
+
 pub struct Sender(State);
 pub struct Receiver(State);
 
+/// Determines how often a channel's keys are ratcheted.
+///
+/// The keys of each direction of a channel are replaced, deterministically
+/// and without interaction, after `rekey_messages` messages or `rekey_bytes`
+/// bytes (whichever comes first). Once replaced, keys are erased, so that
+/// compromising a channel does not expose the messages it previously carried.
+///
+/// Both ends of a channel must use the same `ChannelSettings`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelSettings {
+    pub rekey_messages: u64,
+    pub rekey_bytes: u64,
+}
+
 struct State {
+    key: [u8; HASH_LENGTH],
     cipher: ChaCha20Poly1305,
     hasher: Hasher,
     lane: Lane,
     nonce: u128,
+    settings: ChannelSettings,
+    messages: u64,
+    bytes: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -90,6 +112,8 @@ impl Sender {
             .cipher
             .encrypt_in_place(&ChaChaNonce::from_slice(&nonce), &[], buffer)
             .unwrap(); // Encrypt `buffer` in place
+
+        self.0.advance(buffer.len());
     }
 
     pub fn authenticate<M>(&mut self, message: &M) -> Result<Vec<u8>, Top<ChannelError>>
@@ -140,6 +164,8 @@ impl Sender {
         let tag = self.0.hasher.finalize(); // .. to obtain `tag`
 
         buffer.extend_from_slice(tag.as_bytes()); // Append `tag` to `buffer`
+
+        self.0.advance(buffer.len());
     }
 }
 
@@ -159,9 +185,14 @@ impl Receiver {
     pub fn decrypt_bytes(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Top<ChannelError>> {
         let nonce = self.0.nonce(); // Generate a new `nonce`
 
-        self.0
+        let plaintext = self
+            .0
             .cipher
-            .decrypt(&ChaChaNonce::from_slice(&nonce), ciphertext)
+            .decrypt(&ChaChaNonce::from_slice(&nonce), ciphertext);
+
+        self.0.advance(ciphertext.len()); // Keep in step with the `Sender`, even if decryption failed
+
+        plaintext
             .map_err(|_| ChannelError::DecryptFailed.into_top())
             .spot(here!())
     }
@@ -184,16 +215,19 @@ impl Receiver {
         ciphertext: &mut Vec<u8>,
     ) -> Result<(), Top<ChannelError>> {
         let nonce = self.0.nonce(); // Generate a new `nonce`
+        let length = ciphertext.len();
 
-        self.0
-            .cipher
-            .decrypt_in_place(
-                &ChaChaNonce::from_slice(&nonce),
-                &[],
-                ciphertext as &mut Vec<u8>,
-            )
+        let result = self.0.cipher.decrypt_in_place(
+            &ChaChaNonce::from_slice(&nonce),
+            &[],
+            ciphertext as &mut Vec<u8>,
+        ); // Decrypt `ciphertext` in place
+
+        self.0.advance(length); // Keep in step with the `Sender`, even if decryption failed
+
+        result
             .map_err(|_| ChannelError::DecryptFailed.into_top())
-            .spot(here!()) // Decrypt `ciphertext` in place
+            .spot(here!())
     }
 
     pub fn authenticate<M>(&mut self, ciphertext: &[u8]) -> Result<M, Top<ChannelError>>
@@ -214,32 +248,39 @@ impl Receiver {
     ) -> Result<&'a [u8], Top<ChannelError>> {
         let nonce = self.0.nonce(); // Generate a new `nonce`
 
+        let authentic = self.0.verify_tag(&nonce, ciphertext);
+        self.0.advance(ciphertext.len()); // Keep in step with the `Sender`, even if authentication failed
+
+        if authentic {
+            Ok(&ciphertext[..ciphertext.len() - HASH_LENGTH])
+        } else {
+            ChannelError::AuthenticateFailed.fail().spot(here!())
+        }
+    }
+}
+
+impl State {
+    fn verify_tag(&mut self, nonce: &[u8; NONCE_LENGTH], ciphertext: &[u8]) -> bool {
         if ciphertext.len() < HASH_LENGTH {
             // If `ciphertext` is shorter than `HASH_LENGTH`..
-            ChannelError::AuthenticateFailed.fail().spot(here!()) // .. then it cannot contain an authentication tag
+            false // .. then it cannot contain an authentication tag
         } else {
             let (message, tag) = ciphertext.split_at(ciphertext.len() - HASH_LENGTH); // Split `ciphertext` into `message` and `tag` (`tag` is always second and `HASH_LENGTH` long)
 
             let tag: [u8; HASH_LENGTH] = tag.try_into().unwrap(); // This is guaranteed to work because `message.len() >= HASH_LENGTH`
             let tag: Hash = tag.into(); // Wrap `tag` into a `Hash`
 
-            self.0.hasher.reset(); // Compute the keyed hash..
-            self.0.hasher.update(&nonce); // of `nonce`..
-            self.0.hasher.update(message); // .. and `buffer`..
+            self.hasher.reset(); // Compute the keyed hash..
+            self.hasher.update(nonce); // of `nonce`..
+            self.hasher.update(message); // .. and `buffer`..
 
-            let digest = self.0.hasher.finalize(); // .. to obtain `digest`
+            let digest = self.hasher.finalize(); // .. to obtain `digest`
 
             // IMPORTANT: The following equality MUST be computed between `Hash`es to ensure constant-time comparison!
-            if tag == digest {
-                Ok(message)
-            } else {
-                ChannelError::AuthenticateFailed.fail().spot(here!()) // .. otherwise, authentication failed
-            }
+            tag == digest
         }
     }
-}
 
-impl State {
     fn nonce(&mut self) -> [u8; NONCE_LENGTH] {
         let mut nonce: [u8; NONCE_LENGTH] = self.nonce.to_be_bytes()[16 - NONCE_LENGTH..]
             .try_into()
@@ -252,13 +293,80 @@ impl State {
 
         nonce
     }
+
+    fn new(key: [u8; HASH_LENGTH], lane: Lane, settings: ChannelSettings) -> Self {
+        State {
+            key,
+            cipher: ChaCha20Poly1305::new(ChaChaKey::from_slice(&key)),
+            hasher: Hasher::new_keyed(&key),
+            lane,
+            nonce: 0,
+            settings,
+            messages: 0,
+            bytes: 0,
+        }
+    }
+
+    // Accounts for a message of `bytes` bytes, ratcheting keys if needed
+    fn advance(&mut self, bytes: usize) {
+        self.messages += 1;
+        self.bytes = self.bytes.saturating_add(bytes as u64);
+
+        if self.messages >= self.settings.rekey_messages || self.bytes >= self.settings.rekey_bytes
+        {
+            self.ratchet();
+        }
+    }
+
+    fn ratchet(&mut self) {
+        let mut hasher = Hasher::new_derive_key(RATCHET_CONTEXT);
+        hasher.update(&self.key);
+        hasher.update(&[self.lane as u8]);
+
+        let mut key = *hasher.finalize().as_bytes();
+        hasher.zeroize();
+
+        // Dropping the previous `cipher` erases its key
+        self.key.zeroize();
+        self.hasher.zeroize();
+
+        self.key = key;
+        self.cipher = ChaCha20Poly1305::new(ChaChaKey::from_slice(&self.key));
+        self.hasher = Hasher::new_keyed(&self.key);
+
+        key.zeroize();
+
+        self.messages = 0;
+        self.bytes = 0;
+    }
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        ChannelSettings {
+            rekey_messages: 1 << 20,
+            rekey_bytes: 1 << 30,
+        }
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.hasher.zeroize();
+    }
 }
 
 pub fn channel(key: SharedKey, role: Role) -> (Sender, Receiver) {
-    let key = key.to_bytes();
+    channel_with_settings(key, role, Default::default())
+}
 
-    let cipher_key = ChaChaKey::from_slice(&key);
-    let hasher_key = key;
+pub fn channel_with_settings(
+    key: SharedKey,
+    role: Role,
+    settings: ChannelSettings,
+) -> (Sender, Receiver) {
+    let mut key = key.to_bytes();
 
     // Corresponding ends of opposite roles must match
     let (sender_lane, receiver_lane) = match role {
@@ -266,19 +374,10 @@ pub fn channel(key: SharedKey, role: Role) -> (Sender, Receiver) {
         Role::Odd => (Lane::Low, Lane::High),
     };
 
-    let sender = Sender(State {
-        cipher: ChaCha20Poly1305::new(cipher_key),
-        hasher: Hasher::new_keyed(&hasher_key),
-        lane: sender_lane,
-        nonce: 0,
-    });
+    let sender = Sender(State::new(key, sender_lane, settings.clone()));
+    let receiver = Receiver(State::new(key, receiver_lane, settings));
 
-    let receiver = Receiver(State {
-        cipher: ChaCha20Poly1305::new(cipher_key),
-        hasher: Hasher::new_keyed(&hasher_key),
-        lane: receiver_lane,
-        nonce: 0,
-    });
+    key.zeroize();
 
     (sender, receiver)
 }
@@ -289,6 +388,13 @@ mod tests {
     use crate::crypto::primitives::exchange::KeyPair;
 
     fn setup() -> ((Sender, Receiver), (Sender, Receiver)) {
+        setup_with_settings(Default::default(), Default::default())
+    }
+
+    fn setup_with_settings(
+        alice_settings: ChannelSettings,
+        bob_settings: ChannelSettings,
+    ) -> ((Sender, Receiver), (Sender, Receiver)) {
         let alice_keypair = KeyPair::random();
         let bob_keypair = KeyPair::random();
 
@@ -299,9 +405,9 @@ mod tests {
 
         let (bob_shared_key, bob_role) = bob_keypair.exchange(alice_public_key);
 
-        let alice_channel = channel(alice_shared_key, alice_role);
+        let alice_channel = channel_with_settings(alice_shared_key, alice_role, alice_settings);
 
-        let bob_channel = channel(bob_shared_key, bob_role);
+        let bob_channel = channel_with_settings(bob_shared_key, bob_role, bob_settings);
 
         (alice_channel, bob_channel)
     }
@@ -389,4 +495,102 @@ mod tests {
 
         assert_eq!(plaintext, 34u32);
     }
+
+    #[test]
+    fn ratchet_messages() {
+        let settings = ChannelSettings {
+            rekey_messages: 3,
+            rekey_bytes: u64::MAX,
+        };
+
+        let ((mut alice_sender, mut alice_receiver), (mut bob_sender, mut bob_receiver)) =
+            setup_with_settings(settings.clone(), settings);
+
+        for message in 0..128u32 {
+            let ciphertext = alice_sender.encrypt(&message).unwrap();
+            let plaintext: u32 = bob_receiver.decrypt(&ciphertext[..]).unwrap();
+
+            assert_eq!(plaintext, message);
+
+            let ciphertext = bob_sender.authenticate(&message).unwrap();
+            let plaintext: u32 = alice_receiver.authenticate(&ciphertext[..]).unwrap();
+
+            assert_eq!(plaintext, message);
+        }
+    }
+
+    #[test]
+    fn ratchet_bytes() {
+        let settings = ChannelSettings {
+            rekey_messages: u64::MAX,
+            rekey_bytes: 100,
+        };
+
+        let ((mut alice_sender, _), (_, mut bob_receiver)) =
+            setup_with_settings(settings.clone(), settings);
+
+        for length in 0..64 {
+            let message = vec![length as u8; length];
+
+            let mut ciphertext = alice_sender.encrypt_bytes(&message);
+            bob_receiver
+                .decrypt_bytes_in_place(&mut ciphertext)
+                .unwrap();
+
+            assert_eq!(ciphertext, message);
+        }
+    }
+
+    #[test]
+    fn ratchet_compromise_then_correct() {
+        let settings = ChannelSettings {
+            rekey_messages: 1,
+            rekey_bytes: u64::MAX,
+        };
+
+        let ((mut alice_sender, _), (_, mut bob_receiver)) =
+            setup_with_settings(settings.clone(), settings);
+
+        let mut ciphertext = alice_sender.encrypt(&33u32).unwrap();
+        ciphertext[3] = ciphertext[3].wrapping_add(1);
+
+        let _ = bob_receiver.decrypt::<u32>(&ciphertext[..]);
+
+        let ciphertext = alice_sender.encrypt(&34u32).unwrap();
+        let plaintext: u32 = bob_receiver.decrypt(&ciphertext[..]).unwrap();
+
+        assert_eq!(plaintext, 34u32);
+    }
+
+    #[test]
+    fn ratchet_mismatched_settings() {
+        let ((mut alice_sender, _), (_, mut bob_receiver)) = setup_with_settings(
+            ChannelSettings {
+                rekey_messages: 2,
+                rekey_bytes: u64::MAX,
+            },
+            ChannelSettings {
+                rekey_messages: 3,
+                rekey_bytes: u64::MAX,
+            },
+        );
+
+        for message in 0..2u32 {
+            let ciphertext = alice_sender.encrypt(&message).unwrap();
+            bob_receiver.decrypt::<u32>(&ciphertext[..]).unwrap();
+        }
+
+        let ciphertext = alice_sender.encrypt(&2u32).unwrap();
+        assert!(bob_receiver.decrypt::<u32>(&ciphertext[..]).is_err());
+    }
+
+    #[test]
+    fn ratchet_replaces_keys() {
+        let ((mut alice_sender, _), _) = setup();
+
+        let key = alice_sender.0.key;
+        alice_sender.0.ratchet();
+
+        assert_ne!(alice_sender.0.key, key);
+    }
 }
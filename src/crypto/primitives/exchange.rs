@@ -1,22 +1,36 @@
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Error as FmtError, Formatter};
 use x25519_dalek::{
     EphemeralSecret as XEphemeralSecret, PublicKey as XPublicKey, SharedSecret as XSharedSecret,
+    StaticSecret as XStaticSecret,
 };
+use zeroize::Zeroize;
 
+pub const SECRET_KEY_LENGTH: usize = 32;
 pub const PUBLIC_KEY_LENGTH: usize = 32;
 pub const SHARED_KEY_LENGTH: usize = 32;
 
+/// An ephemeral x25519 keypair, consumed by its first `exchange`.
+///
+/// The secret keys of `KeyPair`s, `StaticKeyPair`s and `SharedKey`s
+/// are wiped from memory when they are dropped.
 pub struct KeyPair {
     public: XPublicKey,
     secret: XEphemeralSecret,
 }
 
+/// A reusable `KeyPair`, whose `PublicKey` can be published ahead of time
+/// for remote hosts to `exchange` against.
+pub struct StaticKeyPair {
+    public: XPublicKey,
+    secret: XStaticSecret,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PublicKey(XPublicKey);
 
-pub struct SharedKey(XSharedSecret);
+pub struct SharedKey([u8; SHARED_KEY_LENGTH]);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
@@ -38,19 +52,37 @@ impl KeyPair {
     }
 
     pub fn exchange(self, remote: PublicKey) -> (SharedKey, Role) {
-        let shared_key = SharedKey(self.secret.diffie_hellman(&remote.0));
-
-        // If `self.public.to_bytes() == remote.to_bytes()`, then a
-        // remote host maliciously echoed `self.public`, and will not
-        // be able to decrypt or authenticate messages. In any case,
-        // communication is compromised and potentially leaked.
-        let role = if self.public.to_bytes() > remote.to_bytes() {
-            Role::Even
-        } else {
-            Role::Odd
-        };
+        let shared_key = SharedKey::from_secret(self.secret.diffie_hellman(&remote.0));
+        (shared_key, role(&self.public, &remote))
+    }
+}
+
+impl StaticKeyPair {
+    pub fn random() -> Self {
+        let secret = XStaticSecret::new(OsRng);
+        let public = XPublicKey::from(&secret);
 
-        (shared_key, role)
+        StaticKeyPair { public, secret }
+    }
+
+    /// Deterministically derives a `StaticKeyPair` from a 32-byte `seed`.
+    ///
+    /// `seed` is used directly as x25519 secret key: it must be
+    /// uniformly random and kept secret.
+    pub fn from_seed(seed: &[u8; SECRET_KEY_LENGTH]) -> Self {
+        let secret = XStaticSecret::from(*seed);
+        let public = XPublicKey::from(&secret);
+
+        StaticKeyPair { public, secret }
+    }
+
+    pub fn public(&self) -> PublicKey {
+        PublicKey(self.public)
+    }
+
+    pub fn exchange(&self, remote: PublicKey) -> (SharedKey, Role) {
+        let shared_key = SharedKey::from_secret(self.secret.diffie_hellman(&remote.0));
+        (shared_key, role(&self.public, &remote))
     }
 }
 
@@ -65,8 +97,54 @@ impl PublicKey {
 }
 
 impl SharedKey {
+    /// Generates a uniformly random `SharedKey`, bound to no exchange
+    /// (e.g., to encrypt data that only the local host can decrypt).
+    pub fn random() -> Self {
+        let mut key = [0u8; SHARED_KEY_LENGTH];
+        OsRng.fill_bytes(&mut key);
+
+        SharedKey(key)
+    }
+
+    fn from_secret(secret: XSharedSecret) -> Self {
+        SharedKey(secret.to_bytes())
+    }
+
+    /// Derives a new `SharedKey`, bound to `context` and `transcript`.
+    ///
+    /// Keys derived under different `context`s or `transcript`s are
+    /// independent: `context` should be a hardcoded, globally unique string.
+    pub fn derive(&self, context: &str, transcript: &[u8]) -> SharedKey {
+        let mut hasher = blake3::Hasher::new_derive_key(context);
+        hasher.update(&self.0);
+        hasher.update(transcript);
+
+        let shared_key = SharedKey(*hasher.finalize().as_bytes());
+        hasher.zeroize();
+
+        shared_key
+    }
+
     pub(in crate::crypto::primitives) fn to_bytes(&self) -> [u8; SHARED_KEY_LENGTH] {
-        self.0.to_bytes()
+        self.0
+    }
+}
+
+impl Drop for SharedKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+fn role(local: &XPublicKey, remote: &PublicKey) -> Role {
+    // If `local.to_bytes() == remote.to_bytes()`, then a
+    // remote host maliciously echoed `local`, and will not
+    // be able to decrypt or authenticate messages. In any case,
+    // communication is compromised and potentially leaked.
+    if local.to_bytes() > remote.to_bytes() {
+        Role::Even
+    } else {
+        Role::Odd
     }
 }
 
@@ -111,6 +189,26 @@ mod tests {
         assert_ne!(alice_role, bob_role);
     }
 
+    #[test]
+    fn static_correct() {
+        let alice_keypair = KeyPair::random();
+        let bob_keypair = StaticKeyPair::from_seed(&[42u8; SECRET_KEY_LENGTH]);
+
+        let alice_public = alice_keypair.public();
+        let bob_public = bob_keypair.public();
+
+        assert_eq!(
+            StaticKeyPair::from_seed(&[42u8; SECRET_KEY_LENGTH]).public(),
+            bob_public
+        );
+
+        let (alice_shared, alice_role) = alice_keypair.exchange(bob_public);
+        let (bob_shared, bob_role) = bob_keypair.exchange(alice_public);
+
+        assert_eq!(alice_shared.to_bytes(), bob_shared.to_bytes());
+        assert_ne!(alice_role, bob_role);
+    }
+
     #[test]
     fn compromise_public_keys() {
         let alice_keypair = KeyPair::random();
@@ -133,6 +231,36 @@ mod tests {
         assert_ne!(alice_shared.to_bytes(), bob_shared.to_bytes());
     }
 
+    #[test]
+    fn derive() {
+        let alice_keypair = KeyPair::random();
+        let bob_keypair = KeyPair::random();
+
+        let alice_public = alice_keypair.public();
+        let bob_public = bob_keypair.public();
+
+        let (alice_shared, _) = alice_keypair.exchange(bob_public);
+        let (bob_shared, _) = bob_keypair.exchange(alice_public);
+
+        let alice_derived = alice_shared.derive("test context", b"transcript");
+        let bob_derived = bob_shared.derive("test context", b"transcript");
+
+        assert_eq!(alice_derived.to_bytes(), bob_derived.to_bytes());
+        assert_ne!(alice_derived.to_bytes(), alice_shared.to_bytes());
+
+        assert_ne!(
+            alice_shared.derive("test context", b"other").to_bytes(),
+            alice_derived.to_bytes()
+        );
+
+        assert_ne!(
+            alice_shared
+                .derive("other context", b"transcript")
+                .to_bytes(),
+            alice_derived.to_bytes()
+        );
+    }
+
     #[test]
     fn man_in_the_middle() {
         let alice_keypair = KeyPair::random();
@@ -0,0 +1,355 @@
+use crate::crypto::primitives::hash::{Hash, Hasher};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Serialize};
+
+// Leaves, internal nodes, empty trees and roots are hashed under distinct
+// prefixes, so that no leaf can be passed off as an internal node (or vice versa)
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+const EMPTY_PREFIX: u8 = 2;
+const ROOT_PREFIX: u8 = 3;
+
+/// A binary hash tree over an ordered sequence of leaves.
+///
+/// Layers are built bottom-up by hashing pairs of adjacent nodes.
+/// When a layer has an odd number of nodes, its last node is carried
+/// to the next layer unchanged. The root commits to the number of leaves,
+/// so that a `Proof` cannot be relabelled to a different position.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    layers: Vec<Vec<Hash>>,
+}
+
+/// A proof that a leaf belongs to a `MerkleTree` at a given position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof {
+    index: usize,
+    width: usize,
+    path: Vec<Hash>,
+}
+
+#[derive(Doom)]
+pub enum MerkleError {
+    #[doom(description("Proof does not match the provided root"))]
+    MismatchedRoot,
+    #[doom(description("Malformed proof"))]
+    MalformedProof,
+    #[doom(description("Failed to serialize: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+}
+
+impl MerkleTree {
+    /// Builds a `MerkleTree` whose leaves are the serializations of `items`.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of any item fails, a `SerializeFailed`
+    /// error variant will be returned.
+    pub fn new<'a, I, T>(items: I) -> Result<Self, Top<MerkleError>>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a + Serialize,
+    {
+        let leaves = items.into_iter().map(leaf).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(MerkleTree::from_leaves(leaves))
+    }
+
+    /// Builds a `MerkleTree` whose leaves are the raw byte strings in `leaves`.
+    pub fn from_raw<I, L>(leaves: I) -> Self
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<[u8]>,
+    {
+        let leaves = leaves
+            .into_iter()
+            .map(|raw| leaf_raw(raw.as_ref()))
+            .collect();
+
+        MerkleTree::from_leaves(leaves)
+    }
+
+    fn from_leaves(leaves: Vec<Hash>) -> Self {
+        let mut layers = vec![leaves];
+
+        while layers.last().unwrap().len() > 1 {
+            let layer = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|chunk| match chunk {
+                    [left, right] => node(left, right),
+                    [carry] => *carry,
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            layers.push(layer);
+        }
+
+        MerkleTree { layers }
+    }
+
+    /// Returns the number of leaves in this `MerkleTree`.
+    pub fn len(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers[0].is_empty()
+    }
+
+    pub fn root(&self) -> Hash {
+        let top = match self.layers.last().unwrap().first() {
+            Some(top) => *top,
+            None => empty(),
+        };
+
+        root(self.len(), &top)
+    }
+
+    /// Returns a `Proof` that the `index`-th leaf belongs to this `MerkleTree`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not smaller than `len()`.
+    pub fn prove(&self, index: usize) -> Proof {
+        assert!(
+            index < self.len(),
+            "Called `MerkleTree::prove` with an out-of-bounds index"
+        );
+
+        let mut path = Vec::with_capacity(self.layers.len() - 1);
+        let mut position = index;
+
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(position ^ 1) {
+                path.push(*sibling);
+            }
+
+            position /= 2;
+        }
+
+        Proof {
+            index,
+            width: self.len(),
+            path,
+        }
+    }
+}
+
+impl Proof {
+    /// Returns the position of the proven leaf in its `MerkleTree`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Verifies that the serialization of `item` is the `index()`-th leaf
+    /// of the `MerkleTree` with root `root`.
+    pub fn verify<T>(&self, root: Hash, item: &T) -> Result<(), Top<MerkleError>>
+    where
+        T: Serialize,
+    {
+        self.verify_leaf(root, leaf(item)?)
+    }
+
+    /// Verifies that `leaf` is the `index()`-th leaf of the
+    /// `MerkleTree` with root `root`.
+    pub fn verify_raw(&self, root: Hash, leaf: &[u8]) -> Result<(), Top<MerkleError>> {
+        self.verify_leaf(root, leaf_raw(leaf))
+    }
+
+    fn verify_leaf(&self, root: Hash, leaf: Hash) -> Result<(), Top<MerkleError>> {
+        if self.index >= self.width {
+            return MerkleError::MalformedProof.fail().spot(here!());
+        }
+
+        let mut path = self.path.iter();
+
+        let mut hash = leaf;
+        let mut position = self.index;
+        let mut width = self.width;
+
+        while width > 1 {
+            // The last node of an odd layer has no sibling, and is carried over
+            if position % 2 == 1 || position + 1 < width {
+                let sibling = match path.next() {
+                    Some(sibling) => sibling,
+                    None => return MerkleError::MalformedProof.fail().spot(here!()),
+                };
+
+                hash = if position % 2 == 0 {
+                    node(&hash, sibling)
+                } else {
+                    node(sibling, &hash)
+                };
+            }
+
+            position /= 2;
+            width = (width + 1) / 2;
+        }
+
+        if path.next().is_some() {
+            return MerkleError::MalformedProof.fail().spot(here!());
+        }
+
+        if self::root(self.width, &hash) == root {
+            Ok(())
+        } else {
+            MerkleError::MismatchedRoot.fail().spot(here!())
+        }
+    }
+}
+
+fn leaf<T>(item: &T) -> Result<Hash, Top<MerkleError>>
+where
+    T: Serialize,
+{
+    let item = bincode::serialize(item)
+        .map_err(MerkleError::serialize_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
+
+    Ok(leaf_raw(&item))
+}
+
+fn leaf_raw(leaf: &[u8]) -> Hash {
+    let mut hasher = Hasher::new();
+    hasher.update_raw(&[LEAF_PREFIX]);
+    hasher.update_raw(leaf);
+    hasher.finalize()
+}
+
+fn node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Hasher::new();
+    hasher.update_raw(&[NODE_PREFIX]);
+    hasher.update_raw(&left.to_bytes());
+    hasher.update_raw(&right.to_bytes());
+    hasher.finalize()
+}
+
+fn empty() -> Hash {
+    let mut hasher = Hasher::new();
+    hasher.update_raw(&[EMPTY_PREFIX]);
+    hasher.finalize()
+}
+
+fn root(width: usize, top: &Hash) -> Hash {
+    let mut hasher = Hasher::new();
+    hasher.update_raw(&[ROOT_PREFIX]);
+    hasher.update_raw(&(width as u64).to_le_bytes());
+    hasher.update_raw(&top.to_bytes());
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct() {
+        for size in 1..=33u32 {
+            let items = (0..size).collect::<Vec<_>>();
+            let tree = MerkleTree::new(&items).unwrap();
+
+            assert_eq!(tree.len(), size as usize);
+
+            for (index, item) in items.iter().enumerate() {
+                let proof = tree.prove(index);
+                proof.verify(tree.root(), item).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn raw() {
+        let leaves = [b"alpha".to_vec(), b"beta".to_vec(), b"gamma".to_vec()];
+        let tree = MerkleTree::from_raw(&leaves);
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            tree.prove(index).verify_raw(tree.root(), leaf).unwrap();
+        }
+
+        assert!(tree.prove(0).verify_raw(tree.root(), b"beta").is_err());
+    }
+
+    #[test]
+    fn empty_root() {
+        let tree = MerkleTree::from_raw(Vec::<Vec<u8>>::new());
+
+        assert!(tree.is_empty());
+        assert_ne!(tree.root(), MerkleTree::from_raw([b""]).root());
+    }
+
+    #[test]
+    fn domain_separation() {
+        let tree = MerkleTree::from_raw([&b"left"[..], &b"right"[..]]);
+
+        let left = leaf_raw(b"left").to_bytes();
+        let right = leaf_raw(b"right").to_bytes();
+
+        let mut forged = Vec::new();
+        forged.extend_from_slice(&left);
+        forged.extend_from_slice(&right);
+
+        assert_ne!(MerkleTree::from_raw([forged]).root(), tree.root());
+    }
+
+    #[test]
+    fn compromise_item() {
+        let items = (0..10u32).collect::<Vec<_>>();
+        let tree = MerkleTree::new(&items).unwrap();
+
+        assert!(tree.prove(3).verify(tree.root(), &4u32).is_err());
+    }
+
+    #[test]
+    fn compromise_proof() {
+        let items = (0..10u32).collect::<Vec<_>>();
+        let tree = MerkleTree::new(&items).unwrap();
+
+        let mut proof = tree.prove(3);
+        proof.index = 2;
+        assert!(proof.verify(tree.root(), &3u32).is_err());
+
+        let mut proof = tree.prove(3);
+        proof.width = 4;
+        assert!(proof.verify(tree.root(), &3u32).is_err());
+
+        let mut proof = tree.prove(3);
+        proof.path.pop();
+        assert!(proof.verify(tree.root(), &3u32).is_err());
+
+        let mut proof = tree.prove(9);
+        proof.path.push(proof.path[0]);
+        assert!(proof.verify(tree.root(), &9u32).is_err());
+    }
+
+    #[test]
+    fn compromise_width() {
+        let items = (0..3u32).collect::<Vec<_>>();
+        let tree = MerkleTree::new(&items).unwrap();
+
+        // The last leaf of a 3-leaf tree is carried over to the root, so its
+        // path is also valid for the second leaf of a 2-leaf tree
+        let mut proof = tree.prove(2);
+        proof.index = 1;
+        proof.width = 2;
+
+        assert!(proof.verify(tree.root(), &2u32).is_err());
+    }
+
+    #[test]
+    fn serialize() {
+        let items = (0..10u32).collect::<Vec<_>>();
+        let tree = MerkleTree::new(&items).unwrap();
+
+        let proof = tree.prove(7);
+
+        let bytes = bincode::serialize(&proof).unwrap();
+        let proof: Proof = bincode::deserialize(&bytes).unwrap();
+
+        proof.verify(tree.root(), &7u32).unwrap();
+    }
+}
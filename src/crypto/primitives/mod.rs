@@ -3,6 +3,10 @@ mod adapters;
 pub mod channel;
 pub mod exchange;
 pub mod hash;
+pub mod merkle;
 pub mod multi;
 pub mod sign;
+pub mod text;
+pub mod threshold;
+pub mod vrf;
 pub mod work;
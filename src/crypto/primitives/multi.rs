@@ -1,7 +1,13 @@
-use crate::crypto::primitives::adapters::{BlstError, BlstErrorAdapter};
-use blst::min_pk::{
-    AggregateSignature as BlstAggregateSignature, PublicKey as BlstPublicKey,
-    SecretKey as BlstSecretKey, Signature as BlstSignature,
+use crate::crypto::primitives::{
+    adapters::{BlstError, BlstErrorAdapter},
+    text::{self, TextError},
+};
+use blst::{
+    blst_scalar,
+    min_pk::{
+        AggregateSignature as BlstAggregateSignature, PublicKey as BlstPublicKey,
+        SecretKey as BlstSecretKey, Signature as BlstSignature,
+    },
 };
 use doomstack::{here, Doom, ResultExt, Top};
 use rand::{rngs::OsRng, CryptoRng, RngCore};
@@ -9,18 +15,30 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     cmp::{Ord, Ordering, PartialOrd},
     fmt,
-    fmt::{Debug, Formatter},
+    fmt::{Debug, Display, Formatter},
     hash::{Hash, Hasher},
+    str::FromStr,
 };
+use zeroize::Zeroize;
 
 pub const PUBLIC_KEY_LENGTH: usize = 96;
 pub const SECRET_KEY_LENGTH: usize = 32;
+pub const SEED_LENGTH: usize = 32;
 pub const SIGNATURE_LENGTH: usize = 192;
 
 pub const KEYPAIR_LENGTH: usize = PUBLIC_KEY_LENGTH + SECRET_KEY_LENGTH;
 
 const BLST_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const TEXT_PREFIX: &str = "msig";
+
+// Bit length of the random coefficients used by `Signature::batch_verify_raw`
+const BATCH_RANDOMNESS_BITS: usize = 64;
 
+/// A BLS keypair.
+///
+/// The secret key of a `KeyPair` is wiped from memory when the `KeyPair`
+/// is dropped.
 pub struct KeyPair {
     public: BlstPublicKey,
     secret: BlstSecretKey,
@@ -41,6 +59,8 @@ pub enum MultiError {
     #[doom(description("Failed to `aggregate` signatures: {}", source))]
     #[doom(wrap(aggregate_failed))]
     AggregateFailed { source: BlstError },
+    #[doom(description("Incorrect batch size"))]
+    IncorrectBatchSize,
     #[doom(description("Incorrect buffer size"))]
     IncorrectBufferSize,
     #[doom(description("Malformed public key: {}", source))]
@@ -69,10 +89,21 @@ impl KeyPair {
     where
         R: CryptoRng + RngCore,
     {
-        let mut seed = [0; 32];
+        let mut seed = [0; SEED_LENGTH];
         rng.fill_bytes(&mut seed);
 
-        let secret = BlstSecretKey::key_gen(&seed, &[]).unwrap();
+        let keypair = KeyPair::from_seed(&seed);
+        seed.zeroize();
+
+        keypair
+    }
+
+    /// Deterministically derives a `KeyPair` from a 32-byte `seed`.
+    ///
+    /// `seed` is used as input keying material for BLS key generation:
+    /// it must be uniformly random and kept secret.
+    pub fn from_seed(seed: &[u8; SEED_LENGTH]) -> Self {
+        let secret = BlstSecretKey::key_gen(seed, &[]).unwrap(); // `seed` is long enough for `key_gen`
         let public = secret.sk_to_pk();
 
         KeyPair { public, secret }
@@ -108,8 +139,12 @@ impl KeyPair {
         let mut keypair_bytes = [0u8; KEYPAIR_LENGTH];
         let (public_bytes, secret_bytes) = keypair_bytes.split_at_mut(PUBLIC_KEY_LENGTH);
 
+        let mut secret = self.secret.to_bytes();
+
         public_bytes.copy_from_slice(&self.public.serialize());
-        secret_bytes.copy_from_slice(&self.secret.to_bytes());
+        secret_bytes.copy_from_slice(&secret);
+
+        secret.zeroize();
 
         keypair_bytes
     }
@@ -149,6 +184,21 @@ impl KeyPair {
         let signature = self.secret.sign(&message, BLST_DST, &[]);
         Ok(Signature(signature))
     }
+
+    /// Proves possession of this `KeyPair`'s secret key.
+    ///
+    /// The proof is a signature of this `KeyPair`'s public key under a
+    /// dedicated ciphersuite: it can be checked using `Signature::verify_possession`,
+    /// but never verifies as a regular `Signature` (and vice versa).
+    pub fn prove_possession(&self) -> Signature {
+        self.sign_dst(&self.public.serialize(), POP_DST)
+    }
+
+    // Signs `message` under the ciphersuite identified by `dst`, allowing other
+    // primitives to reuse the same keys without producing valid `Signature`s
+    pub(in crate::crypto::primitives) fn sign_dst(&self, message: &[u8], dst: &[u8]) -> Signature {
+        Signature(self.secret.sign(message, dst, &[]))
+    }
 }
 
 impl PublicKey {
@@ -246,6 +296,11 @@ impl Signature {
     /// is signed using its matching PrivateKey and (ii) the `Signature` is the
     /// aggregate of (and only of) those individual signatures.
     ///
+    /// Aggregate verification is only sound if every `PublicKey` in `signers`
+    /// comes with a proof of possession of its secret key, otherwise it is
+    /// vulnerable to rogue-key attacks. `KeyCard`s always carry a verified
+    /// proof of possession: prefer verifying against those.
+    ///
     /// # Errors
     ///
     /// If the serialization of the message fails, a `SerializeFailed`
@@ -297,6 +352,179 @@ impl Signature {
             .map_err(Doom::into_top)
             .spot(here!())
     }
+
+    /// Verifies an aggregate `Signature` over distinct messages.
+    ///
+    /// Verification succeeds if and only if the `Signature` is the aggregate
+    /// of (and only of) the individual signatures of every message in `entries`
+    /// by its matching `PublicKey`. Unlike `verify_raw`, every signer can sign
+    /// a different message.
+    ///
+    /// As with `verify_raw`, every `PublicKey` in `entries` must come
+    /// with a proof of possession of its secret key.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of any message fails, a `SerializeFailed`
+    /// error variant will be returned. If the verification fails for
+    /// any reason, `VerifyFailed` will be returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use talk::crypto::primitives::multi::{Signature, KeyPair};
+    ///
+    /// let alice = KeyPair::random();
+    /// let bob = KeyPair::random();
+    ///
+    /// let signature = Signature::aggregate([
+    ///     alice.sign_raw(&1u32).unwrap(),
+    ///     bob.sign_raw(&2u32).unwrap(),
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert!(signature.aggregate_verify_raw([
+    ///     (&alice.public(), &1u32),
+    ///     (&bob.public(), &2u32),
+    /// ]).is_ok());
+    /// ```
+    pub fn aggregate_verify_raw<'e, E, M>(&self, entries: E) -> Result<(), Top<MultiError>>
+    where
+        E: IntoIterator<Item = (&'e PublicKey, &'e M)>,
+        M: 'e + Serialize,
+    {
+        let (signers, messages): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .map(|(signer, message)| (&signer.0, message))
+            .unzip();
+
+        let messages = messages
+            .into_iter()
+            .map(bincode::serialize)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(MultiError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let messages = messages
+            .iter()
+            .map(|message| &message[..])
+            .collect::<Vec<_>>();
+
+        self.0
+            .aggregate_verify(true, &messages[..], BLST_DST, &signers[..], true)
+            .into_result()
+            .map_err(MultiError::verify_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())
+    }
+
+    /// Verifies a batch of independent `Signature`s, each on its own message
+    /// by its own `PublicKey`.
+    ///
+    /// Signatures are checked together, using a random linear combination:
+    /// this is significantly faster than verifying each `Signature` separately,
+    /// but does not reveal which `Signature` is invalid if verification fails.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of any message fails, a `SerializeFailed`
+    /// error variant will be returned. If the verification of any `Signature`
+    /// fails, `VerifyFailed` will be returned.
+    pub fn batch_verify_raw<'m, PI, MI, M, SI>(
+        public_keys: PI,
+        messages: MI,
+        signatures: SI,
+    ) -> Result<(), Top<MultiError>>
+    where
+        PI: IntoIterator<Item = PublicKey>,
+        MI: IntoIterator<Item = &'m M>,
+        M: 'm + Serialize,
+        SI: IntoIterator<Item = Signature>,
+    {
+        let public_keys = public_keys
+            .into_iter()
+            .map(|public_key| public_key.0)
+            .collect::<Vec<_>>();
+
+        let public_keys = public_keys.iter().collect::<Vec<_>>();
+
+        let messages = messages
+            .into_iter()
+            .map(|message| bincode::serialize(&message))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(MultiError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let messages = messages
+            .iter()
+            .map(|message| &message[..])
+            .collect::<Vec<_>>();
+
+        let signatures = signatures
+            .into_iter()
+            .map(|signature| signature.0)
+            .collect::<Vec<_>>();
+
+        let signatures = signatures.iter().collect::<Vec<_>>();
+
+        if public_keys.len() != messages.len() || messages.len() != signatures.len() {
+            return MultiError::IncorrectBatchSize.fail().spot(here!());
+        }
+
+        let randomness = (0..signatures.len())
+            .map(|_| {
+                let mut scalar = blst_scalar::default();
+
+                // Coefficients must be non-zero, lest the matching `Signature` goes unchecked
+                let coefficient = OsRng.next_u64().max(1);
+                scalar.b[..8].copy_from_slice(&coefficient.to_le_bytes());
+
+                scalar
+            })
+            .collect::<Vec<_>>();
+
+        BlstSignature::verify_multiple_aggregate_signatures(
+            &messages[..],
+            BLST_DST,
+            &public_keys[..],
+            true,
+            &signatures[..],
+            true,
+            &randomness[..],
+            BATCH_RANDOMNESS_BITS,
+        )
+        .into_result()
+        .map_err(MultiError::verify_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())
+    }
+
+    /// Verifies a proof of possession of the secret key of `signer`,
+    /// as produced by `KeyPair::prove_possession`.
+    ///
+    /// # Errors
+    ///
+    /// If the verification fails for any reason, a `VerifyFailed`
+    /// error variant will be returned.
+    pub fn verify_possession(&self, signer: &PublicKey) -> Result<(), Top<MultiError>> {
+        self.verify_dst(signer, &signer.to_bytes(), POP_DST)
+            .map_err(MultiError::verify_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())
+    }
+
+    // Counterpart of `KeyPair::sign_dst`
+    pub(in crate::crypto::primitives) fn verify_dst(
+        &self,
+        signer: &PublicKey,
+        message: &[u8],
+        dst: &[u8],
+    ) -> Result<(), BlstError> {
+        self.0
+            .verify(true, message, dst, &[], &signer.0, true)
+            .into_result()
+    }
 }
 
 impl Signer for PublicKey {
@@ -349,6 +577,21 @@ impl Debug for Signature {
     }
 }
 
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(&text::encode(TEXT_PREFIX, &self.to_bytes()))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Top<TextError>;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = text::decode(TEXT_PREFIX, text)?;
+        Signature::from_bytes(&bytes).pot(TextError::MalformedPayload, here!())
+    }
+}
+
 impl Hash for PublicKey {
     fn hash<H>(&self, state: &mut H)
     where
@@ -384,7 +627,11 @@ impl Serialize for KeyPair {
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(&self.to_bytes())
+        let mut bytes = self.to_bytes();
+        let result = serializer.serialize_bytes(&bytes);
+        bytes.zeroize();
+
+        result
     }
 }
 
@@ -603,6 +850,105 @@ mod tests {
         }
     }
 
+    #[test]
+    fn aggregate_correct() {
+        let alice = KeyPair::random();
+        let bob = KeyPair::random();
+        let carl = KeyPair::random();
+
+        let signature = Signature::aggregate([
+            alice.sign_raw(&1u32).unwrap(),
+            bob.sign_raw(&2u32).unwrap(),
+            carl.sign_raw(&3u32).unwrap(),
+        ])
+        .unwrap();
+
+        signature
+            .aggregate_verify_raw([
+                (&alice.public(), &1u32),
+                (&bob.public(), &2u32),
+                (&carl.public(), &3u32),
+            ])
+            .unwrap();
+    }
+
+    #[test]
+    fn aggregate_compromise_message() {
+        let alice = KeyPair::random();
+        let bob = KeyPair::random();
+
+        let signature =
+            Signature::aggregate([alice.sign_raw(&1u32).unwrap(), bob.sign_raw(&2u32).unwrap()])
+                .unwrap();
+
+        assert!(signature
+            .aggregate_verify_raw([(&alice.public(), &2u32), (&bob.public(), &1u32)])
+            .is_err());
+    }
+
+    #[test]
+    fn batch_correct() {
+        let keypairs = (0..16).map(|_| KeyPair::random()).collect::<Vec<_>>();
+        let messages = (0..16u32).collect::<Vec<_>>();
+
+        let signatures = keypairs
+            .iter()
+            .zip(messages.iter())
+            .map(|(keypair, message)| keypair.sign_raw(message).unwrap())
+            .collect::<Vec<_>>();
+
+        Signature::batch_verify_raw(
+            keypairs.iter().map(KeyPair::public),
+            messages.iter(),
+            signatures,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn batch_compromise_message() {
+        let keypairs = (0..16).map(|_| KeyPair::random()).collect::<Vec<_>>();
+        let mut messages = (0..16u32).collect::<Vec<_>>();
+
+        let signatures = keypairs
+            .iter()
+            .zip(messages.iter())
+            .map(|(keypair, message)| keypair.sign_raw(message).unwrap())
+            .collect::<Vec<_>>();
+
+        messages[7] = 42;
+
+        assert!(Signature::batch_verify_raw(
+            keypairs.iter().map(KeyPair::public),
+            messages.iter(),
+            signatures,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn possession_correct() {
+        let keypair = KeyPair::random();
+        let proof = keypair.prove_possession();
+
+        proof.verify_possession(&keypair.public()).unwrap();
+    }
+
+    #[test]
+    fn possession_domain_separation() {
+        let keypair = KeyPair::random();
+        let public = keypair.public();
+
+        let proof = keypair.prove_possession();
+        assert!(proof.verify_raw([&public], &public).is_err());
+        assert!(proof
+            .verify_dst(&public, &public.to_bytes(), BLST_DST)
+            .is_err());
+
+        let signature = keypair.sign_dst(&public.to_bytes(), BLST_DST);
+        assert!(signature.verify_possession(&public).is_err());
+    }
+
     #[test]
     fn serialize_keypair() {
         let original = KeyPair::random();
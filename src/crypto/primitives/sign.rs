@@ -1,7 +1,8 @@
+use crate::crypto::primitives::text::{self, TextError};
 use doomstack::{here, Doom, ResultExt, Top};
 use ed25519_dalek::{
-    Keypair as EdKeyPair, PublicKey as EdPublicKey, Signature as EdSignature, Signer as EdSigner,
-    Verifier as EdVerifier,
+    Keypair as EdKeyPair, PublicKey as EdPublicKey, SecretKey as EdSecretKey,
+    Signature as EdSignature, Signer as EdSigner, Verifier as EdVerifier,
 };
 use rand::{rngs::OsRng, CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
@@ -9,12 +10,24 @@ use std::{
     cmp::{Ord, Ordering, PartialOrd},
     convert::TryInto,
     fmt,
-    fmt::{Debug, Formatter},
+    fmt::{Debug, Display, Formatter},
     hash::{Hash, Hasher},
+    str::FromStr,
 };
 
 pub use ed25519_dalek::{KEYPAIR_LENGTH, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH};
 
+const TEXT_PREFIX: &str = "sig";
+
+/// An ed25519 keypair.
+///
+/// This keypair can be used directly to sign messages via
+/// [`KeyPair::sign_raw`]. The emmitted [`Signature`]s can be
+/// verified against its [`PublicKey`] (see the respective
+/// documentation for details).
+///
+/// The secret key of a `KeyPair` is wiped from memory when the `KeyPair`
+/// is dropped.
 #[derive(Serialize, Deserialize)]
 pub struct KeyPair(EdKeyPair);
 
@@ -52,12 +65,6 @@ pub enum SignError {
     },
 }
 
-/// An ed25519 keypair.
-///
-/// This keypair can be used directly to sign messages via
-/// [`KeyPair::sign_raw`]. The emmitted [`Signature`]s can be
-/// verified against its [`PublicKey`] (see the respective
-/// documentation for details)
 impl KeyPair {
     /// Generates a random `KeyPair` to be used for signing.
     pub fn random() -> Self {
@@ -72,6 +79,17 @@ impl KeyPair {
         KeyPair(keypair)
     }
 
+    /// Deterministically derives a `KeyPair` from a 32-byte `seed`.
+    ///
+    /// `seed` is used directly as ed25519 secret key: it must be
+    /// uniformly random and kept secret.
+    pub fn from_seed(seed: &[u8; SECRET_KEY_LENGTH]) -> Self {
+        let secret = EdSecretKey::from_bytes(seed).unwrap(); // `seed` has the correct length
+        let public = EdPublicKey::from(&secret);
+
+        KeyPair(EdKeyPair { secret, public })
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Top<SignError>> {
         if bytes.len() != KEYPAIR_LENGTH {
             return SignError::IncorrectBufferSize.fail().spot(here!());
@@ -332,6 +350,21 @@ impl Debug for Signature {
     }
 }
 
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(&text::encode(TEXT_PREFIX, &self.to_bytes()))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Top<TextError>;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = text::decode(TEXT_PREFIX, text)?;
+        Signature::from_bytes(&bytes).pot(TextError::MalformedPayload, here!())
+    }
+}
+
 impl Hash for PublicKey {
     fn hash<H>(&self, state: &mut H)
     where
@@ -0,0 +1,209 @@
+use doomstack::{here, Doom, ResultExt, Top};
+
+// Textual encodings are laid out as `prefix || SEPARATOR || base58(payload || checksum)`,
+// where `checksum` is the first `CHECKSUM_LENGTH` bytes of the hash of `prefix`,
+// `SEPARATOR` and `payload`. As `SEPARATOR` is not part of the base58 alphabet,
+// the prefix is unambiguously delimited.
+const SEPARATOR: char = '_';
+const CHECKSUM_LENGTH: usize = 4;
+
+#[derive(Doom)]
+pub enum TextError {
+    #[doom(description("Checksum does not match"))]
+    BadChecksum,
+    #[doom(description("Malformed base58: {}", source))]
+    #[doom(wrap(malformed_base58))]
+    MalformedBase58 { source: bs58::decode::Error },
+    #[doom(description("Malformed payload"))]
+    MalformedPayload,
+    #[doom(description("Missing prefix (expected `{}`)", expected))]
+    MissingPrefix { expected: &'static str },
+    #[doom(description("Text is too short to carry a checksum"))]
+    TooShort,
+    #[doom(description("Wrong prefix (expected `{}`, found `{}`)", expected, found))]
+    WrongPrefix {
+        expected: &'static str,
+        found: String,
+    },
+}
+
+pub(in crate::crypto) fn encode(prefix: &str, payload: &[u8]) -> String {
+    let mut body = payload.to_vec();
+    body.extend_from_slice(&checksum(prefix, payload));
+
+    format!(
+        "{}{}{}",
+        prefix,
+        SEPARATOR,
+        bs58::encode(body).into_string()
+    )
+}
+
+pub(in crate::crypto) fn decode(
+    prefix: &'static str,
+    text: &str,
+) -> Result<Vec<u8>, Top<TextError>> {
+    let (found, body) = match text.split_once(SEPARATOR) {
+        Some(split) => split,
+        None => {
+            return TextError::MissingPrefix { expected: prefix }
+                .fail()
+                .spot(here!())
+        }
+    };
+
+    if found != prefix {
+        return TextError::WrongPrefix {
+            expected: prefix,
+            found: found.to_string(),
+        }
+        .fail()
+        .spot(here!());
+    }
+
+    let mut payload = bs58::decode(body)
+        .into_vec()
+        .map_err(TextError::malformed_base58)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
+
+    if payload.len() < CHECKSUM_LENGTH {
+        return TextError::TooShort.fail().spot(here!());
+    }
+
+    let received = payload.split_off(payload.len() - CHECKSUM_LENGTH);
+
+    if received != checksum(prefix, &payload) {
+        return TextError::BadChecksum.fail().spot(here!());
+    }
+
+    Ok(payload)
+}
+
+fn checksum(prefix: &str, payload: &[u8]) -> [u8; CHECKSUM_LENGTH] {
+    let mut hasher = blake3::Hasher::new();
+
+    hasher.update(prefix.as_bytes());
+    hasher.update(&[SEPARATOR as u8]);
+    hasher.update(payload);
+
+    let mut checksum = [0u8; CHECKSUM_LENGTH];
+    checksum.copy_from_slice(&hasher.finalize().as_bytes()[..CHECKSUM_LENGTH]);
+
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{
+        primitives::{multi::Signature as MultiSignature, sign::Signature as SignSignature},
+        Identity, KeyCard, KeyChain, Statement,
+    };
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct TestStatement;
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    #[test]
+    fn round_trip() {
+        let keychain = KeyChain::random();
+        let keycard = keychain.keycard();
+
+        let identity = keycard.identity();
+        let text = identity.to_string();
+        assert!(text.starts_with("id_"));
+        assert_eq!(text.parse::<Identity>().unwrap(), identity);
+
+        let text = keycard.to_string();
+        assert!(text.starts_with("keycard_"));
+
+        let parsed = text.parse::<KeyCard>().unwrap();
+        assert_eq!(parsed, keycard);
+        assert_eq!(parsed.encryption_key(), keycard.encryption_key());
+
+        let signature = keychain.sign(&TestStatement).unwrap();
+        let text = signature.to_string();
+        assert!(text.starts_with("sig_"));
+        assert_eq!(text.parse::<SignSignature>().unwrap(), signature);
+
+        let signature = keychain.multisign(&TestStatement).unwrap();
+        let text = signature.to_string();
+        assert!(text.starts_with("msig_"));
+        assert_eq!(text.parse::<MultiSignature>().unwrap(), signature);
+    }
+
+    #[test]
+    fn wrong_prefix() {
+        let identity = KeyChain::random().identity();
+        let text = identity.to_string().replacen("id_", "sig_", 1);
+
+        match text.parse::<SignSignature>().unwrap_err().top() {
+            TextError::BadChecksum => (),
+            error => panic!("unexpected error upon parsing relabeled text: {}", error),
+        }
+
+        match identity
+            .to_string()
+            .parse::<SignSignature>()
+            .unwrap_err()
+            .top()
+        {
+            TextError::WrongPrefix { expected, found } => {
+                assert_eq!(*expected, "sig");
+                assert_eq!(found, "id");
+            }
+            error => panic!("unexpected error upon parsing wrong prefix: {}", error),
+        }
+
+        match "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi"
+            .parse::<Identity>()
+            .unwrap_err()
+            .top()
+        {
+            TextError::MissingPrefix { .. } => (),
+            error => panic!("unexpected error upon parsing missing prefix: {}", error),
+        }
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let text = KeyChain::random().identity().to_string();
+
+        let last = text.chars().last().unwrap();
+        let replacement = if last == '2' { '3' } else { '2' };
+
+        let mut corrupted = text[..text.len() - 1].to_string();
+        corrupted.push(replacement);
+
+        match corrupted.parse::<Identity>().unwrap_err().top() {
+            TextError::BadChecksum => (),
+            error => panic!("unexpected error upon parsing corrupted text: {}", error),
+        }
+
+        match "id_0OIl".parse::<Identity>().unwrap_err().top() {
+            TextError::MalformedBase58 { .. } => (),
+            error => panic!("unexpected error upon parsing invalid base58: {}", error),
+        }
+
+        match "id_2".parse::<Identity>().unwrap_err().top() {
+            TextError::TooShort => (),
+            error => panic!("unexpected error upon parsing short text: {}", error),
+        }
+    }
+
+    #[test]
+    fn malformed_payload() {
+        let text = encode("id", &[0u8; 16]);
+
+        match text.parse::<Identity>().unwrap_err().top() {
+            TextError::MalformedPayload => (),
+            error => panic!("unexpected error upon parsing short payload: {}", error),
+        }
+    }
+}
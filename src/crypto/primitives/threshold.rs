@@ -0,0 +1,919 @@
+use crate::crypto::{
+    primitives::adapters::{BlstError, BlstErrorAdapter},
+    Statement,
+};
+use blst::{
+    blst_bendian_from_scalar, blst_fr, blst_fr_add, blst_fr_from_scalar, blst_fr_from_uint64,
+    blst_fr_inverse, blst_fr_mul, blst_fr_sub, blst_p2, blst_p2_add_or_double, blst_p2_affine,
+    blst_p2_affine_serialize, blst_p2_deserialize, blst_p2_from_affine, blst_p2_mult,
+    blst_p2_to_affine, blst_scalar, blst_scalar_from_fr, blst_scalar_from_le_bytes,
+    min_pk::{PublicKey as BlstPublicKey, SecretKey as BlstSecretKey, Signature as BlstSignature},
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    convert::TryInto,
+    fmt,
+    fmt::{Debug, Formatter},
+};
+use zeroize::Zeroize;
+
+pub const INDEX_LENGTH: usize = 4;
+pub const PUBLIC_KEY_LENGTH: usize = 96;
+pub const SECRET_KEY_LENGTH: usize = 32;
+pub const SIGNATURE_LENGTH: usize = 192;
+
+pub const KEY_SHARE_LENGTH: usize = INDEX_LENGTH + PUBLIC_KEY_LENGTH + SECRET_KEY_LENGTH;
+
+const BLST_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// One share of a threshold key, held by a single signer.
+///
+/// A `KeyShare` signs on behalf of the group: any `threshold` distinct
+/// `PartialSignature`s on the same message can be combined into a single
+/// `Signature`, verifiable against the group's `PublicKey`.
+///
+/// The secret key of a `KeyShare` is wiped from memory when the
+/// `KeyShare` is dropped.
+pub struct KeyShare {
+    index: u32,
+    public: BlstPublicKey,
+    secret: BlstSecretKey,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(BlstPublicKey);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+    index: u32,
+    signature: Signature,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Signature(BlstSignature);
+
+#[derive(Doom)]
+pub enum ThresholdError {
+    #[doom(description("Failed to `combine` partial signatures: {}", source))]
+    #[doom(wrap(combine_failed))]
+    CombineFailed { source: BlstError },
+    #[doom(description("Duplicate partial signature index"))]
+    DuplicateIndex,
+    #[doom(description("Incorrect buffer size"))]
+    IncorrectBufferSize,
+    #[doom(description("Not enough partial signatures to reach the threshold"))]
+    InsufficientShares,
+    #[doom(description("Invalid threshold parameters"))]
+    InvalidParameters,
+    #[doom(description("Public key does not match secret key"))]
+    KeyMismatch,
+    #[doom(description("Malformed public key: {}", source))]
+    #[doom(wrap(malformed_public_key))]
+    MalformedPublicKey { source: BlstError },
+    #[doom(description("Malformed secret key: {}", source))]
+    #[doom(wrap(malformed_secret_key))]
+    MalformedSecretKey { source: BlstError },
+    #[doom(description("Malformed signature: {}", source))]
+    #[doom(wrap(malformed_signature))]
+    MalformedSignature { source: BlstError },
+    #[doom(description("Failed to serialize: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+    #[doom(description("Failed to `verify` signature: {}", source))]
+    #[doom(wrap(verify_failed))]
+    VerifyFailed { source: BlstError },
+}
+
+impl KeyShare {
+    /// Generates a new threshold key, split in `parties` `KeyShare`s, any
+    /// `threshold` of which are needed to produce a `Signature`.
+    ///
+    /// Returns the group `PublicKey`, the `PublicKey` of each `KeyShare`
+    /// (by index), and the `KeyShare`s (indexed from 1 to `parties`). The
+    /// `PublicKey`s of the `KeyShare`s are public: they allow anyone to verify
+    /// `PartialSignature`s (see `Signature::combine_verified`). This is a
+    /// trusted dealer: whoever runs `deal` learns the group secret, and must
+    /// distribute the `KeyShare`s securely.
+    ///
+    /// # Errors
+    ///
+    /// If `threshold` is zero or larger than `parties`, an `InvalidParameters`
+    /// error variant will be returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use talk::crypto::primitives::threshold::{KeyShare, Signature};
+    ///
+    /// let (group, public_keys, shares) = KeyShare::deal(2, 3).unwrap();
+    ///
+    /// let message: u32 = 1234;
+    ///
+    /// let partials = shares[1..]
+    ///     .iter()
+    ///     .map(|share| share.sign_raw(&message).unwrap());
+    ///
+    /// let signature =
+    ///     Signature::combine_verified_raw(2, &public_keys, partials, &message).unwrap();
+    ///
+    /// assert!(signature.verify_raw(&group, &message).is_ok());
+    /// ```
+    pub fn deal(
+        threshold: usize,
+        parties: usize,
+    ) -> Result<(PublicKey, Vec<(u32, PublicKey)>, Vec<KeyShare>), Top<ThresholdError>> {
+        KeyShare::deal_from_rng(threshold, parties, &mut OsRng)
+    }
+
+    pub fn deal_from_rng<R>(
+        threshold: usize,
+        parties: usize,
+        rng: &mut R,
+    ) -> Result<(PublicKey, Vec<(u32, PublicKey)>, Vec<KeyShare>), Top<ThresholdError>>
+    where
+        R: CryptoRng + RngCore,
+    {
+        if threshold == 0 || threshold > parties || parties > u32::MAX as usize {
+            return ThresholdError::InvalidParameters.fail().spot(here!());
+        }
+
+        // The group secret is `polynomial[0]`, the secret of
+        // each `KeyShare` is the polynomial evaluated at its index
+        let mut polynomial = (0..threshold).map(|_| random_fr(rng)).collect::<Vec<_>>();
+
+        let group = to_secret_key(&polynomial[0]).sk_to_pk();
+
+        let shares = (1..=(parties as u32))
+            .map(|index| {
+                let mut evaluation = evaluate(polynomial.as_slice(), index);
+
+                let secret = to_secret_key(&evaluation);
+                let public = secret.sk_to_pk();
+
+                evaluation.l.zeroize();
+
+                KeyShare {
+                    index,
+                    public,
+                    secret,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for coefficient in polynomial.iter_mut() {
+            coefficient.l.zeroize();
+        }
+
+        let public_keys = shares
+            .iter()
+            .map(|share| (share.index, share.public()))
+            .collect::<Vec<_>>();
+
+        Ok((PublicKey(group), public_keys, shares))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Top<ThresholdError>> {
+        if bytes.len() != KEY_SHARE_LENGTH {
+            return ThresholdError::IncorrectBufferSize.fail().spot(here!());
+        }
+
+        let (index_bytes, key_bytes) = bytes.split_at(INDEX_LENGTH);
+        let (public_bytes, secret_bytes) = key_bytes.split_at(PUBLIC_KEY_LENGTH);
+
+        let index = u32::from_le_bytes(index_bytes.try_into().unwrap());
+
+        let public = BlstPublicKey::from_bytes(public_bytes)
+            .map_err(Into::<BlstError>::into)
+            .map_err(ThresholdError::malformed_public_key)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let secret = BlstSecretKey::from_bytes(secret_bytes)
+            .map_err(Into::<BlstError>::into)
+            .map_err(ThresholdError::malformed_secret_key)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        if secret.sk_to_pk() != public {
+            return ThresholdError::KeyMismatch.fail().spot(here!());
+        }
+
+        Ok(KeyShare {
+            index,
+            public,
+            secret,
+        })
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the `PublicKey` against which this `KeyShare`'s
+    /// `PartialSignature`s can be verified.
+    pub fn public(&self) -> PublicKey {
+        PublicKey(self.public)
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_SHARE_LENGTH] {
+        let mut share_bytes = [0u8; KEY_SHARE_LENGTH];
+        let (index_bytes, key_bytes) = share_bytes.split_at_mut(INDEX_LENGTH);
+        let (public_bytes, secret_bytes) = key_bytes.split_at_mut(PUBLIC_KEY_LENGTH);
+
+        let mut secret = self.secret.to_bytes();
+
+        index_bytes.copy_from_slice(&self.index.to_le_bytes());
+        public_bytes.copy_from_slice(&self.public.serialize());
+        secret_bytes.copy_from_slice(&secret);
+
+        secret.zeroize();
+
+        share_bytes
+    }
+
+    pub fn sign<S>(&self, message: &S) -> Result<PartialSignature, Top<ThresholdError>>
+    where
+        S: Statement,
+    {
+        self.sign_raw(&(S::SCOPE, S::HEADER, message))
+    }
+
+    /// Signs a message using this `KeyShare`.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of the message fails, a `SerializeFailed`
+    /// error variant will be returned.
+    pub fn sign_raw<T>(&self, message: &T) -> Result<PartialSignature, Top<ThresholdError>>
+    where
+        T: Serialize,
+    {
+        let message = bincode::serialize(message)
+            .map_err(ThresholdError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let signature = self.secret.sign(&message, BLST_DST, &[]);
+
+        Ok(PartialSignature {
+            index: self.index,
+            signature: Signature(signature),
+        })
+    }
+}
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Top<ThresholdError>> {
+        if bytes.len() != PUBLIC_KEY_LENGTH {
+            return ThresholdError::IncorrectBufferSize.fail().spot(here!());
+        }
+
+        let public_key = BlstPublicKey::from_bytes(bytes)
+            .map_err(Into::<BlstError>::into)
+            .map_err(ThresholdError::malformed_public_key)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        Ok(PublicKey(public_key))
+    }
+
+    pub fn to_bytes(&self) -> [u8; PUBLIC_KEY_LENGTH] {
+        self.0.serialize()
+    }
+}
+
+impl PartialSignature {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn verify<S>(&self, public_key: &PublicKey, message: &S) -> Result<(), Top<ThresholdError>>
+    where
+        S: Statement,
+    {
+        self.verify_raw(public_key, &(S::SCOPE, S::HEADER, message))
+    }
+
+    /// Verifies the `PartialSignature` of a message against the `PublicKey`
+    /// of the `KeyShare` that produced it (see `KeyShare::public`).
+    pub fn verify_raw<M>(
+        &self,
+        public_key: &PublicKey,
+        message: &M,
+    ) -> Result<(), Top<ThresholdError>>
+    where
+        M: Serialize,
+    {
+        self.signature.verify_raw(public_key, message)
+    }
+}
+
+impl Signature {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Top<ThresholdError>> {
+        if bytes.len() != SIGNATURE_LENGTH {
+            return ThresholdError::IncorrectBufferSize.fail().spot(here!());
+        }
+
+        let signature = BlstSignature::from_bytes(bytes)
+            .map_err(Into::<BlstError>::into)
+            .map_err(ThresholdError::malformed_signature)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        Ok(Signature(signature))
+    }
+
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LENGTH] {
+        self.0.serialize()
+    }
+
+    /// Combines `PartialSignature`s into a single `Signature` by Lagrange
+    /// interpolation in the exponent.
+    ///
+    /// Only the first `threshold` `PartialSignature`s (by index) are used.
+    /// `PartialSignature`s are not verified: combining an invalid
+    /// `PartialSignature` results in an invalid `Signature`. To combine
+    /// `PartialSignature`s from untrusted sources, use `combine_verified`.
+    ///
+    /// # Errors
+    ///
+    /// If fewer than `threshold` `PartialSignature`s are provided, an
+    /// `InsufficientShares` error variant will be returned. If two
+    /// `PartialSignature`s share the same index, `DuplicateIndex` will be
+    /// returned.
+    pub fn combine<I>(threshold: usize, partials: I) -> Result<Self, Top<ThresholdError>>
+    where
+        I: IntoIterator<Item = PartialSignature>,
+    {
+        let mut partials = partials.into_iter().collect::<Vec<_>>();
+        partials.sort_by_key(|partial| partial.index);
+
+        if partials
+            .windows(2)
+            .any(|window| window[0].index == window[1].index)
+        {
+            return ThresholdError::DuplicateIndex.fail().spot(here!());
+        }
+
+        if threshold == 0 || partials.len() < threshold {
+            return ThresholdError::InsufficientShares.fail().spot(here!());
+        }
+
+        partials.truncate(threshold);
+
+        let indices = partials
+            .iter()
+            .map(|partial| partial.index)
+            .collect::<Vec<_>>();
+
+        let mut accumulator = blst_p2::default(); // Point at infinity
+
+        for partial in partials.iter() {
+            let mut affine = blst_p2_affine::default();
+
+            let result =
+                unsafe { blst_p2_deserialize(&mut affine, partial.signature.to_bytes().as_ptr()) };
+
+            result
+                .into_result()
+                .map_err(ThresholdError::combine_failed)
+                .map_err(Doom::into_top)
+                .spot(here!())?;
+
+            let mut scalar = blst_scalar::default();
+            let coefficient = lagrange_coefficient(partial.index, indices.as_slice());
+
+            let mut point = blst_p2::default();
+            let mut term = blst_p2::default();
+
+            let sum = accumulator;
+
+            unsafe {
+                blst_scalar_from_fr(&mut scalar, &coefficient);
+                blst_p2_from_affine(&mut point, &affine);
+                blst_p2_mult(&mut term, &point, scalar.b.as_ptr(), 255);
+                blst_p2_add_or_double(&mut accumulator, &sum, &term);
+            }
+        }
+
+        let mut affine = blst_p2_affine::default();
+        let mut bytes = [0u8; SIGNATURE_LENGTH];
+
+        unsafe {
+            blst_p2_to_affine(&mut affine, &accumulator);
+            blst_p2_affine_serialize(bytes.as_mut_ptr(), &affine);
+        }
+
+        Signature::from_bytes(&bytes)
+    }
+
+    pub fn combine_verified<S, I>(
+        threshold: usize,
+        public_keys: &[(u32, PublicKey)],
+        partials: I,
+        message: &S,
+    ) -> Result<Self, Top<ThresholdError>>
+    where
+        S: Statement,
+        I: IntoIterator<Item = PartialSignature>,
+    {
+        Signature::combine_verified_raw(
+            threshold,
+            public_keys,
+            partials,
+            &(S::SCOPE, S::HEADER, message),
+        )
+    }
+
+    /// Combines the valid `PartialSignature`s of a message into a single
+    /// `Signature`, as `combine` does.
+    ///
+    /// Each `PartialSignature` is verified against the `PublicKey` of its
+    /// index in `public_keys` (as returned by `KeyShare::deal`). Invalid
+    /// `PartialSignature`s, `PartialSignature`s whose index is not in
+    /// `public_keys`, and duplicates are discarded. Verification stops as
+    /// soon as `threshold` valid `PartialSignature`s are found.
+    ///
+    /// # Errors
+    ///
+    /// If fewer than `threshold` valid `PartialSignature`s are provided,
+    /// an `InsufficientShares` error variant will be returned.
+    pub fn combine_verified_raw<M, I>(
+        threshold: usize,
+        public_keys: &[(u32, PublicKey)],
+        partials: I,
+        message: &M,
+    ) -> Result<Self, Top<ThresholdError>>
+    where
+        M: Serialize,
+        I: IntoIterator<Item = PartialSignature>,
+    {
+        let mut valid: Vec<PartialSignature> = Vec::with_capacity(threshold);
+
+        for partial in partials {
+            if valid.len() >= threshold {
+                break;
+            }
+
+            if valid.iter().any(|other| other.index == partial.index) {
+                continue;
+            }
+
+            let public_key = match public_keys
+                .iter()
+                .find(|(index, _)| *index == partial.index)
+            {
+                Some((_, public_key)) => public_key,
+                None => continue,
+            };
+
+            if partial.verify_raw(public_key, message).is_ok() {
+                valid.push(partial);
+            }
+        }
+
+        Signature::combine(threshold, valid)
+    }
+
+    pub fn verify<S>(&self, group: &PublicKey, message: &S) -> Result<(), Top<ThresholdError>>
+    where
+        S: Statement,
+    {
+        self.verify_raw(group, &(S::SCOPE, S::HEADER, message))
+    }
+
+    /// Verifies the `Signature` of a message against the group `PublicKey`.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of the message fails, a `SerializeFailed`
+    /// error variant will be returned. If the verification fails for
+    /// any reason, `VerifyFailed` will be returned.
+    pub fn verify_raw<M>(&self, group: &PublicKey, message: &M) -> Result<(), Top<ThresholdError>>
+    where
+        M: Serialize,
+    {
+        let message = bincode::serialize(message)
+            .map_err(ThresholdError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        self.0
+            .verify(true, &message[..], BLST_DST, &[], &group.0, true)
+            .into_result()
+            .map_err(ThresholdError::verify_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())
+    }
+}
+
+fn fr_from_u64(value: u64) -> blst_fr {
+    let mut fr = blst_fr::default();
+
+    unsafe {
+        blst_fr_from_uint64(&mut fr, [value, 0, 0, 0].as_ptr());
+    }
+
+    fr
+}
+
+fn random_fr<R>(rng: &mut R) -> blst_fr
+where
+    R: CryptoRng + RngCore,
+{
+    // Reducing 512 random bits modulo the group order yields a negligible bias
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes);
+
+    let mut scalar = blst_scalar::default();
+    let mut fr = blst_fr::default();
+
+    unsafe {
+        blst_scalar_from_le_bytes(&mut scalar, bytes.as_ptr(), bytes.len());
+        blst_fr_from_scalar(&mut fr, &scalar);
+    }
+
+    bytes.zeroize();
+    scalar.b.zeroize();
+
+    fr
+}
+
+fn to_secret_key(fr: &blst_fr) -> BlstSecretKey {
+    let mut scalar = blst_scalar::default();
+    let mut bytes = [0u8; SECRET_KEY_LENGTH];
+
+    unsafe {
+        blst_scalar_from_fr(&mut scalar, fr);
+        blst_bendian_from_scalar(bytes.as_mut_ptr(), &scalar);
+    }
+
+    // This fails only if `fr` is zero, which happens with negligible probability
+    let secret = BlstSecretKey::from_bytes(&bytes).unwrap();
+
+    bytes.zeroize();
+    scalar.b.zeroize();
+
+    secret
+}
+
+fn evaluate(polynomial: &[blst_fr], x: u32) -> blst_fr {
+    let x = fr_from_u64(x as u64);
+    let mut y = blst_fr::default();
+
+    // Horner's method
+    for coefficient in polynomial.iter().rev() {
+        let partial = y;
+
+        unsafe {
+            blst_fr_mul(&mut y, &partial, &x);
+        }
+
+        let partial = y;
+
+        unsafe {
+            blst_fr_add(&mut y, &partial, coefficient);
+        }
+    }
+
+    y
+}
+
+fn lagrange_coefficient(index: u32, indices: &[u32]) -> blst_fr {
+    // Coefficient of `index` for interpolation at 0, i.e., the
+    // product of `other / (other - index)` for all other `indices`
+    let x = fr_from_u64(index as u64);
+
+    let mut numerator = fr_from_u64(1);
+    let mut denominator = fr_from_u64(1);
+
+    for other in indices.iter().copied().filter(|other| *other != index) {
+        let other = fr_from_u64(other as u64);
+        let mut difference = blst_fr::default();
+
+        let partial_numerator = numerator;
+        let partial_denominator = denominator;
+
+        unsafe {
+            blst_fr_sub(&mut difference, &other, &x);
+            blst_fr_mul(&mut numerator, &partial_numerator, &other);
+            blst_fr_mul(&mut denominator, &partial_denominator, &difference);
+        }
+    }
+
+    let mut inverse = blst_fr::default();
+    let mut coefficient = blst_fr::default();
+
+    unsafe {
+        blst_fr_inverse(&mut inverse, &denominator);
+        blst_fr_mul(&mut coefficient, &numerator, &inverse);
+    }
+
+    coefficient
+}
+
+impl Debug for PublicKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let bytes = self
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{:02x?}", byte))
+            .collect::<Vec<_>>()
+            .join("");
+
+        if f.alternate() {
+            write!(
+                f,
+                "PublicKey({} ... {})",
+                &bytes[..8],
+                &bytes[bytes.len() - 8..]
+            )
+        } else {
+            write!(f, "PublicKey({})", bytes)
+        }
+    }
+}
+
+impl Debug for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        let bytes = self
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{:02x?}", byte))
+            .collect::<Vec<_>>()
+            .join("");
+
+        if f.alternate() {
+            write!(
+                f,
+                "Signature({} ... {})",
+                &bytes[..8],
+                &bytes[bytes.len() - 8..]
+            )
+        } else {
+            write!(f, "Signature({})", bytes)
+        }
+    }
+}
+
+impl Serialize for KeyShare {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut bytes = self.to_bytes();
+        let result = serializer.serialize_bytes(&bytes);
+        bytes.zeroize();
+
+        result
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyShare {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{Error, Visitor};
+
+        struct ByteVisitor;
+
+        impl<'de> Visitor<'de> for ByteVisitor {
+            type Value = KeyShare;
+
+            fn expecting(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+                f.write_str(
+                    "byte representation of a bls key share (concatenated index, public and secret key)",
+                )
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                KeyShare::from_bytes(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(ByteVisitor)
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{Error, Visitor};
+
+        struct ByteVisitor;
+
+        impl<'de> Visitor<'de> for ByteVisitor {
+            type Value = PublicKey;
+
+            fn expecting(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+                f.write_str("byte representation of a bls public key")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                PublicKey::from_bytes(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(ByteVisitor)
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{Error, Visitor};
+
+        struct ByteVisitor;
+
+        impl<'de> Visitor<'de> for ByteVisitor {
+            type Value = Signature;
+
+            fn expecting(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+                f.write_str("byte representation of a bls signature")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Signature::from_bytes(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_bytes(ByteVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct() {
+        let (group, _, shares) = KeyShare::deal(3, 5).unwrap();
+        let message: u32 = 1234;
+
+        let partials = shares
+            .iter()
+            .map(|share| share.sign_raw(&message).unwrap())
+            .collect::<Vec<_>>();
+
+        for (share, partial) in shares.iter().zip(partials.iter()) {
+            partial.verify_raw(&share.public(), &message).unwrap();
+        }
+
+        let reference = Signature::combine(3, partials[..3].iter().copied()).unwrap();
+        reference.verify_raw(&group, &message).unwrap();
+
+        // Any subset of `threshold` shares yields the same signature
+        let signature = Signature::combine(3, partials[2..].iter().copied()).unwrap();
+        assert_eq!(signature, reference);
+
+        let signature =
+            Signature::combine(3, [partials[4], partials[0], partials[2]].iter().copied()).unwrap();
+
+        assert_eq!(signature, reference);
+    }
+
+    #[test]
+    fn insufficient() {
+        let (_, _, shares) = KeyShare::deal(3, 5).unwrap();
+        let message: u32 = 1234;
+
+        let partials = shares[..2]
+            .iter()
+            .map(|share| share.sign_raw(&message).unwrap());
+
+        assert!(Signature::combine(3, partials).is_err());
+    }
+
+    #[test]
+    fn duplicate() {
+        let (_, _, shares) = KeyShare::deal(3, 5).unwrap();
+        let message: u32 = 1234;
+
+        let partial = shares[0].sign_raw(&message).unwrap();
+
+        assert!(Signature::combine(3, vec![partial; 3]).is_err());
+    }
+
+    #[test]
+    fn compromise_message() {
+        let (group, _, shares) = KeyShare::deal(3, 5).unwrap();
+
+        let partials = shares
+            .iter()
+            .enumerate()
+            .map(|(index, share)| {
+                let message: u32 = if index == 1 { 1235 } else { 1234 };
+                share.sign_raw(&message).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let signature = Signature::combine(3, partials[..3].iter().copied()).unwrap();
+        assert!(signature.verify_raw(&group, &1234u32).is_err());
+
+        let signature = Signature::combine(3, partials[2..].iter().copied()).unwrap();
+        signature.verify_raw(&group, &1234u32).unwrap();
+    }
+
+    #[test]
+    fn combine_verified() {
+        let (group, public_keys, shares) = KeyShare::deal(3, 5).unwrap();
+        let message: u32 = 1234;
+
+        let mut partials = shares
+            .iter()
+            .map(|share| share.sign_raw(&message).unwrap())
+            .collect::<Vec<_>>();
+
+        // Share 2 signs the wrong message, share 4 is replayed
+        partials[1] = shares[1].sign_raw(&1235u32).unwrap();
+        partials.insert(4, partials[3]);
+
+        for (share, (index, public_key)) in shares.iter().zip(public_keys.iter()) {
+            assert_eq!(share.index(), *index);
+            assert_eq!(share.public(), *public_key);
+        }
+
+        let signature =
+            Signature::combine_verified_raw(3, &public_keys, partials.iter().copied(), &message)
+                .unwrap();
+
+        signature.verify_raw(&group, &message).unwrap();
+
+        // Only partials 2 and 4 are left, one of which is invalid
+        assert!(Signature::combine_verified_raw(
+            2,
+            &public_keys,
+            [partials[1], partials[3], partials[4]],
+            &message
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn key_mismatch() {
+        let (_, _, shares) = KeyShare::deal(2, 2).unwrap();
+
+        let mut bytes = shares[0].to_bytes();
+        bytes[INDEX_LENGTH..INDEX_LENGTH + PUBLIC_KEY_LENGTH]
+            .copy_from_slice(&shares[1].public().to_bytes());
+
+        assert!(KeyShare::from_bytes(&bytes).is_err());
+        assert!(KeyShare::from_bytes(&shares[0].to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(KeyShare::deal(0, 5).is_err());
+        assert!(KeyShare::deal(6, 5).is_err());
+    }
+
+    #[test]
+    fn serialize_key_share() {
+        let (group, _, shares) = KeyShare::deal(1, 1).unwrap();
+        let original = shares.into_iter().next().unwrap();
+
+        let serialized = bincode::serialize(&original).unwrap();
+        let deserialized = bincode::deserialize::<KeyShare>(serialized.as_slice()).unwrap();
+
+        assert_eq!(original.to_bytes(), deserialized.to_bytes());
+
+        let message = 42u64;
+        let partial = deserialized.sign_raw(&message).unwrap();
+
+        let signature = Signature::combine(1, [partial]).unwrap();
+        signature.verify_raw(&group, &message).unwrap();
+    }
+}
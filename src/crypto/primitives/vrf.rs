@@ -0,0 +1,146 @@
+use crate::crypto::primitives::{
+    hash::{Hash, Hasher},
+    multi::{KeyPair, PublicKey, Signature},
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Serialize};
+
+// BLS signatures are unique: for a given `PublicKey` and message, exactly one
+// `Signature` verifies. A VRF `Proof` is a BLS signature under a dedicated
+// ciphersuite (so that no `multi::Signature` can double as a `Proof`), and
+// the VRF `Output` is the hash of the `Proof`.
+const VRF_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_TALK_VRF_";
+const OUTPUT_CONTEXT: &[u8] = b"talk 2022-06-01 vrf::Output";
+
+/// The pseudorandom output of a VRF evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Output(Hash);
+
+/// A proof that an `Output` was correctly evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof(Signature);
+
+#[derive(Doom)]
+pub enum VrfError {
+    #[doom(description("Invalid VRF proof"))]
+    InvalidProof,
+    #[doom(description("VRF output does not match proof"))]
+    MismatchedOutput,
+    #[doom(description("Failed to serialize: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+}
+
+impl Output {
+    pub fn to_hash(&self) -> Hash {
+        self.0
+    }
+}
+
+impl Proof {
+    /// Evaluates the VRF of `keypair` on `message`.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of the message fails, a `SerializeFailed`
+    /// error variant will be returned.
+    pub fn evaluate_raw<T>(keypair: &KeyPair, message: &T) -> Result<(Output, Proof), Top<VrfError>>
+    where
+        T: Serialize,
+    {
+        let message = bincode::serialize(message)
+            .map_err(VrfError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let proof = Proof(keypair.sign_dst(&message, VRF_DST));
+        Ok((proof.output(), proof))
+    }
+
+    /// Verifies that this `Proof` was produced by the holder of `public` on
+    /// `message`, returning the corresponding `Output`.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of the message fails, a `SerializeFailed`
+    /// error variant will be returned. If verification fails, `InvalidProof`
+    /// will be returned.
+    pub fn verify_raw<T>(&self, public: &PublicKey, message: &T) -> Result<Output, Top<VrfError>>
+    where
+        T: Serialize,
+    {
+        let message = bincode::serialize(message)
+            .map_err(VrfError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        self.0
+            .verify_dst(public, &message, VRF_DST)
+            .map_err(|_| VrfError::InvalidProof.into_top())
+            .spot(here!())?;
+
+        Ok(self.output())
+    }
+
+    /// Returns the `Output` proven by this `Proof`.
+    ///
+    /// The `Output` is only meaningful once the `Proof` is verified.
+    pub fn output(&self) -> Output {
+        let mut hasher = Hasher::new();
+        hasher.update_raw(OUTPUT_CONTEXT);
+        hasher.update_raw(&self.0.to_bytes());
+
+        Output(hasher.finalize())
+    }
+}
+
+impl From<Output> for Hash {
+    fn from(output: Output) -> Self {
+        output.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct() {
+        let keypair = KeyPair::random();
+
+        let (output, proof) = Proof::evaluate_raw(&keypair, &42u32).unwrap();
+
+        assert_eq!(proof.verify_raw(&keypair.public(), &42u32).unwrap(), output);
+    }
+
+    #[test]
+    fn deterministic() {
+        let keypair = KeyPair::random();
+
+        let (first, _) = Proof::evaluate_raw(&keypair, &42u32).unwrap();
+        let (second, _) = Proof::evaluate_raw(&keypair, &42u32).unwrap();
+        let (other, _) = Proof::evaluate_raw(&keypair, &43u32).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn compromise_message() {
+        let keypair = KeyPair::random();
+
+        let (_, proof) = Proof::evaluate_raw(&keypair, &42u32).unwrap();
+
+        assert!(proof.verify_raw(&keypair.public(), &43u32).is_err());
+    }
+
+    #[test]
+    fn compromise_signature() {
+        let keypair = KeyPair::random();
+
+        // A regular signature on the same message must not pass as a `Proof`
+        let proof = Proof(keypair.sign_raw(&42u32).unwrap());
+
+        assert!(proof.verify_raw(&keypair.public(), &42u32).is_err());
+    }
+}
@@ -1,17 +1,47 @@
-use crate::crypto::{primitives::hash, Statement};
+use crate::{
+    crypto::{
+        primitives::{hash, hash::Hash},
+        Statement,
+    },
+    sync::fuse::Relay,
+};
 use doomstack::{here, Doom, ResultExt, Top};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
-use std::convert::TryInto;
+use std::{
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+// Number of nonces tried by `Work::estimate` to measure the hash rate
+const ESTIMATE_SAMPLES: u64 = 1 << 12;
+
+// Number of threads of `POOL` (0 stands for the available parallelism)
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+// Searches run on a dedicated pool, so that they never
+// starve other users of `rayon`'s global pool
+static POOL: OnceLock<ThreadPool> = OnceLock::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Work(u64);
 
 #[derive(Doom)]
 pub enum WorkError {
+    #[doom(description("Search cancelled"))]
+    Cancelled,
     #[doom(description("Failed to hash message"))]
     HashFailed,
     #[doom(description("Invalid nonce"))]
     InvalidNonce,
+    #[doom(description("Search failed"))]
+    SearchFailed,
 }
 
 impl Work {
@@ -22,27 +52,123 @@ impl Work {
         Work::new_raw(difficulty, &(S::SCOPE, S::HEADER, message))
     }
 
+    /// Searches for a nonce that meets `difficulty` for `message`.
+    ///
+    /// The nonce space is partitioned across the threads of a dedicated pool
+    /// (see `Work::set_threads`). This call blocks until a nonce is found:
+    /// in asynchronous contexts, use `solve` instead.
     pub fn new_raw<T>(difficulty: u64, message: &T) -> Result<Self, Top<WorkError>>
     where
         T: Serialize,
     {
         let digest = hash::hash(message).pot(WorkError::HashFailed, here!())?;
-        let target = u64::MAX >> difficulty;
 
-        // TODO: Generalize this to the multi-threaded setting
-        for nonce in 0u64.. {
-            let score = u64::from_le_bytes(
-                hash::hash(&(digest, nonce)).unwrap().to_bytes()[0..8]
-                    .try_into()
-                    .unwrap(),
-            );
-
-            if score < target {
-                return Ok(Work(nonce));
-            }
+        let nonce = pool()
+            .install(|| search(digest, difficulty, &AtomicBool::new(false)))
+            .unwrap(); // The search cannot be stopped
+
+        Ok(Work(nonce))
+    }
+
+    /// Searches for a nonce that meets `difficulty` for `message`,
+    /// without blocking the asynchronous runtime.
+    ///
+    /// # Errors
+    ///
+    /// If `relay` is switched off before a nonce is found, the search
+    /// is stopped and a `Cancelled` error variant will be returned.
+    /// If the search fails to complete, a `SearchFailed` error variant
+    /// will be returned.
+    pub async fn solve<S>(
+        difficulty: u64,
+        message: &S,
+        relay: &mut Relay,
+    ) -> Result<Self, Top<WorkError>>
+    where
+        S: Statement,
+    {
+        Work::solve_raw(difficulty, &(S::SCOPE, S::HEADER, message), relay).await
+    }
+
+    pub async fn solve_raw<T>(
+        difficulty: u64,
+        message: &T,
+        relay: &mut Relay,
+    ) -> Result<Self, Top<WorkError>>
+    where
+        T: Serialize,
+    {
+        let digest = hash::hash(message).pot(WorkError::HashFailed, here!())?;
+
+        // Stops the search if the search is cancelled or this future is dropped
+        let stop = Stop(Arc::new(AtomicBool::new(false)));
+        let flag = stop.0.clone();
+
+        let (sender, receiver) = oneshot::channel();
+
+        pool().spawn(move || {
+            let _ = sender.send(search(digest, difficulty, flag.as_ref()));
+        });
+
+        // If `receiver` errs, the search was interrupted before completion
+        let nonce = match relay.map(receiver).await {
+            Some(result) => result
+                .map_err(|_| WorkError::SearchFailed.into_top())
+                .spot(here!())?,
+            None => None,
+        };
+
+        match nonce {
+            Some(nonce) => Ok(Work(nonce)),
+            None => WorkError::Cancelled.fail().spot(here!()),
+        }
+    }
+
+    /// Sets the number of threads used to search for nonces.
+    ///
+    /// Searches run on a dedicated pool, which is built upon the first
+    /// search with `threads` threads (by default, as many as the available
+    /// parallelism). As a result, `set_threads` must be called before any
+    /// nonce is searched for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is 0, or if the pool was already built.
+    pub fn set_threads(threads: usize) {
+        assert!(threads > 0, "called `Work::set_threads` with 0 threads");
+
+        assert!(
+            POOL.get().is_none(),
+            "called `Work::set_threads` after the first search"
+        );
+
+        THREADS.store(threads, Ordering::Relaxed);
+    }
+
+    /// Estimates the time needed on this machine to find a
+    /// nonce that meets `difficulty`.
+    ///
+    /// The estimate is based on a short benchmark of the hash rate of
+    /// a single thread, scaled by the number of threads used to search.
+    pub fn estimate(difficulty: u64) -> Duration {
+        let digest = hash::hash(&()).unwrap();
+
+        let start = Instant::now();
+
+        for nonce in 0..ESTIMATE_SAMPLES {
+            score(digest, nonce);
         }
 
-        unreachable!()
+        let elapsed = start.elapsed().as_secs_f64() / (ESTIMATE_SAMPLES as f64);
+        let rate = (pool().current_num_threads() as f64) / elapsed;
+
+        Duration::from_secs_f64(Work::expected_attempts(difficulty) / rate)
+    }
+
+    /// Returns the expected number of nonces to try
+    /// before finding one that meets `difficulty`.
+    pub fn expected_attempts(difficulty: u64) -> f64 {
+        2f64.powi(difficulty as i32)
     }
 
     pub fn verify<S>(&self, difficulty: u64, message: &S) -> Result<(), Top<WorkError>>
@@ -59,13 +185,7 @@ impl Work {
         let digest = hash::hash(message).pot(WorkError::HashFailed, here!())?;
         let target = u64::MAX >> difficulty;
 
-        let score = u64::from_le_bytes(
-            hash::hash(&(digest, self.0)).unwrap().to_bytes()[0..8]
-                .try_into()
-                .unwrap(),
-        );
-
-        if score < target {
+        if score(digest, self.0) < target {
             Ok(())
         } else {
             WorkError::InvalidNonce.fail().spot(here!())
@@ -73,9 +193,58 @@ impl Work {
     }
 }
 
+fn pool() -> &'static ThreadPool {
+    POOL.get_or_init(|| {
+        let threads = match THREADS.load(Ordering::Relaxed) {
+            0 => thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+            threads => threads,
+        };
+
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("talk-work-{}", index))
+            .build()
+            .expect("failed to build `Work` thread pool")
+    })
+}
+
+struct Stop(Arc<AtomicBool>);
+
+impl Drop for Stop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+// Returns `None` if `stop` is raised before a nonce is found
+fn search(digest: Hash, difficulty: u64, stop: &AtomicBool) -> Option<u64> {
+    let target = u64::MAX >> difficulty;
+
+    let nonce = (0..u64::MAX)
+        .into_par_iter()
+        .find_any(|nonce| stop.load(Ordering::Relaxed) || score(digest, *nonce) < target)?;
+
+    if score(digest, nonce) < target {
+        Some(nonce)
+    } else {
+        None
+    }
+}
+
+fn score(digest: Hash, nonce: u64) -> u64 {
+    u64::from_le_bytes(
+        hash::hash(&(digest, nonce)).unwrap().to_bytes()[0..8]
+            .try_into()
+            .unwrap(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sync::fuse::Fuse;
 
     #[test]
     fn easy() {
@@ -91,4 +260,31 @@ mod tests {
         let work = Work::new_raw(24, &42u32).unwrap();
         work.verify_raw(24, &42u32).unwrap()
     }
+
+    #[tokio::test]
+    async fn solve() {
+        let fuse = Fuse::new();
+        let mut relay = fuse.relay();
+
+        for message in 0u32..8u32 {
+            let work = Work::solve_raw(10, &message, &mut relay).await.unwrap();
+            work.verify_raw(10, &message).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn solve_cancelled() {
+        let fuse = Fuse::new();
+        let mut relay = fuse.relay();
+
+        fuse.burn();
+
+        assert!(Work::solve_raw(60, &42u32, &mut relay).await.is_err());
+    }
+
+    #[test]
+    fn estimate() {
+        assert_eq!(Work::expected_attempts(10), 1024.);
+        assert!(Work::estimate(20) > Work::estimate(10));
+    }
 }
@@ -1,7 +1,8 @@
 use crate::crypto::{
     primitives::multi::{Signature, Signer},
-    Statement,
+    Committee, Identity, Statement,
 };
+use std::collections::HashSet;
 
 struct Entry<'a, R> {
     index: usize,
@@ -38,6 +39,49 @@ where
     }
 }
 
+/// Like `filter_aggregate`, but with entries identified by the `Identity` of
+/// their signer within `committee`.
+///
+/// Returns the aggregate of all valid signatures, along with the `Identity`
+/// of every entry that was filtered out. Entries whose signer does not belong
+/// to `committee` are always filtered out. If a signer appears in more than
+/// one entry, only its first entry is considered.
+pub fn filter_aggregate_committee<'a, S, E>(
+    committee: &Committee,
+    statement: &S,
+    entries: E,
+) -> (Option<Signature>, Vec<Identity>)
+where
+    S: Sync + Statement,
+    E: IntoIterator<Item = (Identity, &'a Signature)>,
+{
+    let mut members = Vec::new();
+    let mut outsiders = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (identity, signature) in entries {
+        match committee.index_of(identity) {
+            Some(index) => {
+                if seen.insert(index) {
+                    members.push((&committee.members()[index], signature));
+                }
+            }
+            None => outsiders.push(identity),
+        }
+    }
+
+    let (signature, exceptions) = filter_aggregate(statement, members.iter().copied());
+
+    let mut exceptions = exceptions
+        .into_iter()
+        .map(|index| members[index].0.identity())
+        .collect::<Vec<_>>();
+
+    exceptions.append(&mut outsiders);
+
+    (signature, exceptions)
+}
+
 fn aggregation_tree<'a, R>(entries: &[Entry<'a, R>], chunk: usize) -> AggregationNode
 where
     R: 'a + Sync + Signer,
@@ -282,6 +326,96 @@ mod tests {
         assert_eq!(exceptions, reference_exceptions);
     }
 
+    #[test]
+    fn committee() {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(32)
+            .collect::<Vec<_>>();
+
+        let committee = Committee::new(keychains.iter().map(KeyChain::keycard));
+        let outsider = KeyChain::random();
+
+        let signatures = keychains
+            .iter()
+            .enumerate()
+            .map(|(index, keychain)| {
+                let signature = keychain
+                    .multisign(if index == 3 {
+                        &TestStatement::Incorrect
+                    } else {
+                        &TestStatement::Correct
+                    })
+                    .unwrap();
+
+                (keychain.keycard().identity(), signature)
+            })
+            .chain(iter::once((
+                outsider.keycard().identity(),
+                outsider.multisign(&TestStatement::Correct).unwrap(),
+            )))
+            .collect::<Vec<_>>();
+
+        let (signature, exceptions) = filter_aggregate_committee(
+            &committee,
+            &TestStatement::Correct,
+            signatures
+                .iter()
+                .map(|(identity, signature)| (*identity, signature)),
+        );
+
+        let valid = signatures
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != 3 && *index != 32)
+            .map(|(_, (identity, _))| &committee.members()[committee.index_of(*identity).unwrap()]);
+
+        signature
+            .unwrap()
+            .verify(valid, &TestStatement::Correct)
+            .unwrap();
+
+        assert_eq!(
+            exceptions,
+            vec![signatures[3].0, outsider.keycard().identity()]
+        );
+    }
+
+    #[test]
+    fn committee_duplicates() {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(8)
+            .collect::<Vec<_>>();
+
+        let committee = Committee::new(keychains.iter().map(KeyChain::keycard));
+
+        let signatures = keychains
+            .iter()
+            .map(|keychain| {
+                (
+                    keychain.keycard().identity(),
+                    keychain.multisign(&TestStatement::Correct).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Every entry is submitted twice
+        let (signature, exceptions) = filter_aggregate_committee(
+            &committee,
+            &TestStatement::Correct,
+            signatures
+                .iter()
+                .chain(signatures.iter())
+                .map(|(identity, signature)| (*identity, signature)),
+        );
+
+        signature
+            .unwrap()
+            .verify(committee.members(), &TestStatement::Correct)
+            .unwrap();
+
+        assert!(exceptions.is_empty());
+    }
+
     #[test]
     fn incorrect() {
         let keychains = iter::repeat_with(KeyChain::random)
@@ -0,0 +1,199 @@
+use crate::crypto::{
+    primitives::sign::{Signature, Signer},
+    procedures::FilterVerifySettings,
+    Statement,
+};
+
+struct Entry<'a, R, M> {
+    index: usize,
+    signer: &'a R,
+    message: &'a M,
+    signature: &'a Signature,
+}
+
+/// Verifies a set of `(signer, message, signature)` entries, returning
+/// the indices of the entries whose signature is invalid.
+///
+/// Entries are split in chunks of at most `settings.chunk_size`, which are
+/// batch-verified in parallel. Chunks that fail batch verification are
+/// recursively bisected until every invalid entry is isolated.
+pub fn filter_verify<'a, E, R, M>(entries: E, settings: FilterVerifySettings) -> Vec<usize>
+where
+    E: IntoIterator<Item = (&'a R, &'a M, &'a Signature)>,
+    R: 'a + Sync + Signer,
+    M: 'a + Sync + Statement,
+{
+    let entries = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (signer, message, signature))| Entry {
+            index,
+            signer,
+            message,
+            signature,
+        })
+        .collect::<Vec<_>>();
+
+    filter_split(entries.as_slice(), settings.chunk_size.max(1))
+}
+
+fn filter_split<'a, R, M>(entries: &[Entry<'a, R, M>], chunk: usize) -> Vec<usize>
+where
+    R: 'a + Sync + Signer,
+    M: 'a + Sync + Statement,
+{
+    if entries.len() > chunk {
+        // Chunks are only verified once small enough, so that no
+        // single batch verification spans the whole set of entries
+        bisect(entries, |half| filter_split(half, chunk))
+    } else {
+        filter_recursion(entries)
+    }
+}
+
+fn filter_recursion<'a, R, M>(entries: &[Entry<'a, R, M>]) -> Vec<usize>
+where
+    R: 'a + Sync + Signer,
+    M: 'a + Sync + Statement,
+{
+    if entries.is_empty() {
+        return Vec::new();
+    }
+
+    let verified = Signature::batch_verify(
+        entries.iter().map(|entry| entry.signer),
+        entries.iter().map(|entry| entry.message),
+        entries.iter().map(|entry| entry.signature),
+    )
+    .is_ok();
+
+    if verified {
+        Vec::new()
+    } else if entries.len() == 1 {
+        vec![entries[0].index]
+    } else {
+        bisect(entries, filter_recursion)
+    }
+}
+
+fn bisect<'a, R, M, F>(entries: &[Entry<'a, R, M>], filter: F) -> Vec<usize>
+where
+    R: 'a + Sync + Signer,
+    M: 'a + Sync + Statement,
+    F: Sync + Fn(&[Entry<'a, R, M>]) -> Vec<usize>,
+{
+    let mid = entries.len() / 2;
+    let (left_entries, right_entries) = entries.split_at(mid);
+
+    let (mut left_exceptions, mut right_exceptions) =
+        rayon::join(|| filter(left_entries), || filter(right_entries));
+
+    left_exceptions.append(&mut right_exceptions);
+    left_exceptions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{KeyCard, KeyChain};
+    use serde::Serialize;
+    use std::iter;
+
+    #[derive(Serialize)]
+    struct TestStatement(u32);
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    fn setup(size: usize) -> (Vec<KeyCard>, Vec<TestStatement>, Vec<Signature>) {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(size)
+            .collect::<Vec<_>>();
+
+        let keycards = keychains.iter().map(KeyChain::keycard).collect();
+
+        let messages = (0..size as u32).map(TestStatement).collect::<Vec<_>>();
+
+        let signatures = keychains
+            .iter()
+            .zip(messages.iter())
+            .map(|(keychain, message)| keychain.sign(message).unwrap())
+            .collect();
+
+        (keycards, messages, signatures)
+    }
+
+    fn entries<'a>(
+        keycards: &'a [KeyCard],
+        messages: &'a [TestStatement],
+        signatures: &'a [Signature],
+    ) -> impl Iterator<Item = (&'a KeyCard, &'a TestStatement, &'a Signature)> {
+        keycards
+            .iter()
+            .zip(messages.iter())
+            .zip(signatures.iter())
+            .map(|((keycard, message), signature)| (keycard, message, signature))
+    }
+
+    #[test]
+    fn empty() {
+        let exceptions = filter_verify(
+            Vec::<(&KeyCard, &TestStatement, &Signature)>::new(),
+            Default::default(),
+        );
+
+        assert!(exceptions.is_empty());
+    }
+
+    #[test]
+    fn correct() {
+        let (keycards, messages, signatures) = setup(128);
+
+        let exceptions = filter_verify(
+            entries(&keycards, &messages, &signatures),
+            FilterVerifySettings { chunk_size: 16 },
+        );
+
+        assert!(exceptions.is_empty());
+    }
+
+    #[test]
+    fn one_incorrect() {
+        let (keycards, mut messages, signatures) = setup(128);
+        messages[33] = TestStatement(u32::MAX);
+
+        let exceptions = filter_verify(
+            entries(&keycards, &messages, &signatures),
+            FilterVerifySettings { chunk_size: 16 },
+        );
+
+        assert_eq!(exceptions, vec![33]);
+    }
+
+    #[test]
+    fn multiple_incorrect() {
+        let (keycards, mut messages, signatures) = setup(128);
+
+        for (index, message) in messages.iter_mut().enumerate() {
+            if index % 3 == 0 {
+                message.0 = u32::MAX;
+            }
+        }
+
+        for chunk_size in [1, 7, 32, 256] {
+            let exceptions = filter_verify(
+                entries(&keycards, &messages, &signatures),
+                FilterVerifySettings { chunk_size },
+            );
+
+            let reference_exceptions = (0..128)
+                .into_iter()
+                .filter(|index| index % 3 == 0)
+                .collect::<Vec<_>>();
+
+            assert_eq!(exceptions, reference_exceptions);
+        }
+    }
+}
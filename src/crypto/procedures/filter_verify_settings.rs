@@ -0,0 +1,10 @@
+#[derive(Debug, Clone)]
+pub struct FilterVerifySettings {
+    pub chunk_size: usize,
+}
+
+impl Default for FilterVerifySettings {
+    fn default() -> Self {
+        FilterVerifySettings { chunk_size: 64 }
+    }
+}
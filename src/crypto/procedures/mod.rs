@@ -1,3 +1,7 @@
 mod filter_aggregate;
+mod filter_verify;
+mod filter_verify_settings;
 
-pub use filter_aggregate::filter_aggregate;
+pub use filter_aggregate::{filter_aggregate, filter_aggregate_committee};
+pub use filter_verify::filter_verify;
+pub use filter_verify_settings::FilterVerifySettings;
@@ -1,13 +1,21 @@
 use serde::{Deserialize, Serialize};
 
+/// The domain in which a `Statement` is signed.
+///
+/// Signatures produced in one `Scope` never verify in another. Applications
+/// built on `talk` should sign under their own `Scope::application`, so that
+/// their `Statement`s cannot be replayed across protocols, even if their
+/// headers collide.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Scope(ScopeId);
 
+// Variants are serialized by position: new variants must only be appended
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 enum ScopeId {
     Talk = 0,
     User = 1,
+    Application([u8; 32]) = 2,
 }
 
 impl Scope {
@@ -15,7 +23,269 @@ impl Scope {
         Scope(ScopeId::User)
     }
 
+    /// Returns the `Scope` of the application identified by `name`.
+    ///
+    /// A `Scope` retains the `blake3` hash of `name`: distinct names
+    /// identify distinct `Scope`s. To avoid clashes, `name` should be
+    /// unique to the application (e.g., its crate name).
+    pub const fn application(name: &str) -> Self {
+        Scope(ScopeId::Application(blake3_hash(name.as_bytes())))
+    }
+
     pub(crate) const fn talk() -> Self {
         Scope(ScopeId::Talk)
     }
 }
+
+// `blake3` (hash mode, 32-byte output), evaluable in `const` contexts
+const BLAKE3_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const BLAKE3_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+const BLAKE3_BLOCK_LENGTH: usize = 64;
+const BLAKE3_CHUNK_LENGTH: usize = 1024;
+
+const BLAKE3_CHUNK_START: u32 = 1 << 0;
+const BLAKE3_CHUNK_END: u32 = 1 << 1;
+const BLAKE3_PARENT: u32 = 1 << 2;
+const BLAKE3_ROOT: u32 = 1 << 3;
+
+const fn blake3_hash(input: &[u8]) -> [u8; 32] {
+    let words = blake3_subtree(input, 0, input.len(), 0, BLAKE3_ROOT);
+
+    let mut hash = [0u8; 32];
+    let mut index = 0;
+
+    while index < 32 {
+        hash[index] = (words[index / 4] >> (8 * (index % 4))) as u8;
+        index += 1;
+    }
+
+    hash
+}
+
+// Returns the chaining value of `input[start..start + length]`, whose
+// first chunk is the `counter`-th chunk of `input`
+const fn blake3_subtree(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    counter: u64,
+    root: u32,
+) -> [u32; 8] {
+    if length <= BLAKE3_CHUNK_LENGTH {
+        return blake3_chunk(input, start, length, counter, root);
+    }
+
+    // The left subtree holds the largest power of two of full chunks
+    let full_chunks = (length - 1) / BLAKE3_CHUNK_LENGTH;
+    let mut left_chunks = 1;
+
+    while left_chunks * 2 <= full_chunks {
+        left_chunks *= 2;
+    }
+
+    let left_length = left_chunks * BLAKE3_CHUNK_LENGTH;
+
+    let left = blake3_subtree(input, start, left_length, counter, 0);
+    let right = blake3_subtree(
+        input,
+        start + left_length,
+        length - left_length,
+        counter + left_chunks as u64,
+        0,
+    );
+
+    let mut block = [0u32; 16];
+    let mut index = 0;
+
+    while index < 8 {
+        block[index] = left[index];
+        block[index + 8] = right[index];
+        index += 1;
+    }
+
+    blake3_compress(
+        BLAKE3_IV,
+        block,
+        0,
+        BLAKE3_BLOCK_LENGTH as u32,
+        BLAKE3_PARENT | root,
+    )
+}
+
+const fn blake3_chunk(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    counter: u64,
+    root: u32,
+) -> [u32; 8] {
+    let blocks = if length == 0 {
+        1
+    } else {
+        (length + BLAKE3_BLOCK_LENGTH - 1) / BLAKE3_BLOCK_LENGTH
+    };
+
+    let mut chaining = BLAKE3_IV;
+    let mut block_index = 0;
+
+    while block_index < blocks {
+        let offset = block_index * BLAKE3_BLOCK_LENGTH;
+
+        let block_length = if length - offset < BLAKE3_BLOCK_LENGTH {
+            length - offset
+        } else {
+            BLAKE3_BLOCK_LENGTH
+        };
+
+        let mut block = [0u32; 16];
+        let mut index = 0;
+
+        while index < block_length {
+            block[index / 4] |= (input[start + offset + index] as u32) << (8 * (index % 4));
+            index += 1;
+        }
+
+        let mut flags = 0;
+
+        if block_index == 0 {
+            flags |= BLAKE3_CHUNK_START;
+        }
+
+        if block_index == blocks - 1 {
+            flags |= BLAKE3_CHUNK_END | root;
+        }
+
+        chaining = blake3_compress(chaining, block, counter, block_length as u32, flags);
+        block_index += 1;
+    }
+
+    chaining
+}
+
+const fn blake3_compress(
+    chaining: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_length: u32,
+    flags: u32,
+) -> [u32; 8] {
+    let mut state = [
+        chaining[0],
+        chaining[1],
+        chaining[2],
+        chaining[3],
+        chaining[4],
+        chaining[5],
+        chaining[6],
+        chaining[7],
+        BLAKE3_IV[0],
+        BLAKE3_IV[1],
+        BLAKE3_IV[2],
+        BLAKE3_IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_length,
+        flags,
+    ];
+
+    let mut block = block;
+    let mut round = 0;
+
+    while round < 7 {
+        state = blake3_g(state, 0, 4, 8, 12, block[0], block[1]);
+        state = blake3_g(state, 1, 5, 9, 13, block[2], block[3]);
+        state = blake3_g(state, 2, 6, 10, 14, block[4], block[5]);
+        state = blake3_g(state, 3, 7, 11, 15, block[6], block[7]);
+        state = blake3_g(state, 0, 5, 10, 15, block[8], block[9]);
+        state = blake3_g(state, 1, 6, 11, 12, block[10], block[11]);
+        state = blake3_g(state, 2, 7, 8, 13, block[12], block[13]);
+        state = blake3_g(state, 3, 4, 9, 14, block[14], block[15]);
+
+        let mut permuted = [0u32; 16];
+        let mut index = 0;
+
+        while index < 16 {
+            permuted[index] = block[BLAKE3_PERMUTATION[index]];
+            index += 1;
+        }
+
+        block = permuted;
+        round += 1;
+    }
+
+    let mut output = [0u32; 8];
+    let mut index = 0;
+
+    while index < 8 {
+        output[index] = state[index] ^ state[index + 8];
+        index += 1;
+    }
+
+    output
+}
+
+const fn blake3_g(
+    mut state: [u32; 16],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+    x: u32,
+    y: u32,
+) -> [u32; 16] {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(x);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(y);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn application() {
+        assert_eq!(
+            Scope::application("consensus"),
+            Scope::application("consensus")
+        );
+        assert_ne!(
+            Scope::application("consensus"),
+            Scope::application("storage")
+        );
+        assert_ne!(Scope::application(""), Scope::user());
+
+        // Up to 4 chunks, spanning two levels of the tree
+        for length in [0, 1, 64, 65, 1024, 1025, 2048, 3073] {
+            let name = (0..length)
+                .map(|index| (b'a' + (index % 26) as u8) as char)
+                .collect::<String>();
+
+            assert_eq!(
+                Scope::application(&name),
+                Scope(ScopeId::Application(
+                    *blake3::hash(name.as_bytes()).as_bytes()
+                ))
+            );
+        }
+
+        for scope in [
+            Scope::talk(),
+            Scope::user(),
+            Scope::application("consensus"),
+        ] {
+            let bytes = bincode::serialize(&scope).unwrap();
+            assert_eq!(bincode::deserialize::<Scope>(&bytes).unwrap(), scope);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::crypto::{
+    primitives::{
+        channel,
+        exchange::{KeyPair as ExchangeKeyPair, PublicKey as ExchangePublicKey},
+        sign::Signature as SignSignature,
+    },
+    Identity, KeyCard, KeyChain, Scope, Statement, TalkHeader,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Doom)]
+pub enum SealedBoxError {
+    #[doom(description("Failed to deserialize: {:?}", source))]
+    #[doom(wrap(deserialize_failed))]
+    DeserializeFailed { source: bincode::Error },
+    #[doom(description("Invalid sender signature"))]
+    InvalidSender,
+    #[doom(description("Recipient `KeyCard` carries no encryption key"))]
+    MissingEncryptionKey,
+    #[doom(description("Failed to open sealed box"))]
+    OpenFailed,
+    #[doom(description("Failed to serialize: {:?}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+    #[doom(description("Sealed box carries no sender signature"))]
+    Unauthenticated,
+}
+
+// A sealed box is a `bincode`-serialized `SealedBox`: `ciphertext` is the
+// encryption of a `Contents` under the key exchanged between `ephemeral`
+// and the recipient's encryption key.
+#[derive(Serialize, Deserialize)]
+struct SealedBox {
+    ephemeral: ExchangePublicKey,
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Contents {
+    message: Vec<u8>,
+    signature: Option<SignSignature>,
+}
+
+// Binding `recipient` and `ephemeral` prevents the recipient of an
+// authenticated sealed box from re-sealing it to someone else
+#[derive(Serialize)]
+struct Sealing<'a> {
+    recipient: Identity,
+    ephemeral: ExchangePublicKey,
+    message: &'a [u8],
+}
+
+impl KeyCard {
+    /// Encrypts `message` so that only the holder of this `KeyCard`'s
+    /// `KeyChain` can `open` it.
+    ///
+    /// The sender of the resulting sealed box is anonymous: to
+    /// authenticate the sender, use `seal_from`.
+    ///
+    /// # Errors
+    ///
+    /// If this `KeyCard` carries no encryption key, a `MissingEncryptionKey`
+    /// error variant will be returned.
+    pub fn seal<M>(&self, message: &M) -> Result<Vec<u8>, Top<SealedBoxError>>
+    where
+        M: Serialize,
+    {
+        seal(self, None, message)
+    }
+
+    /// Encrypts `message` so that only the holder of this `KeyCard`'s
+    /// `KeyChain` can `open` it, signing it with `sender`.
+    ///
+    /// The signature is encrypted along with `message`, and is only
+    /// visible to the recipient, who can check it using `open_from`.
+    pub fn seal_from<M>(
+        &self,
+        sender: &KeyChain,
+        message: &M,
+    ) -> Result<Vec<u8>, Top<SealedBoxError>>
+    where
+        M: Serialize,
+    {
+        seal(self, Some(sender), message)
+    }
+}
+
+impl KeyChain {
+    /// Decrypts a sealed box addressed to this `KeyChain`'s `KeyCard`.
+    ///
+    /// Sender signatures, if any, are ignored: to require that the sealed
+    /// box was authenticated by a specific sender, use `open_from`.
+    ///
+    /// # Errors
+    ///
+    /// If `sealed` was not addressed to this `KeyChain`, or was tampered
+    /// with, an `OpenFailed` error variant will be returned.
+    pub fn open<M>(&self, sealed: &[u8]) -> Result<M, Top<SealedBoxError>>
+    where
+        M: DeserializeOwned,
+    {
+        let (_, contents) = self.open_contents(sealed)?;
+        deserialize(&contents.message)
+    }
+
+    /// Decrypts a sealed box addressed to this `KeyChain`'s `KeyCard`,
+    /// verifying that it was sealed by the holder of `sender`.
+    ///
+    /// # Errors
+    ///
+    /// If `sealed` carries no sender signature, an `Unauthenticated` error
+    /// variant will be returned. If the signature was not produced by the
+    /// holder of `sender` for this `KeyChain`, `InvalidSender` will be returned.
+    pub fn open_from<M>(&self, sender: &KeyCard, sealed: &[u8]) -> Result<M, Top<SealedBoxError>>
+    where
+        M: DeserializeOwned,
+    {
+        let (ephemeral, contents) = self.open_contents(sealed)?;
+
+        let signature = match contents.signature {
+            Some(signature) => signature,
+            None => return SealedBoxError::Unauthenticated.fail().spot(here!()),
+        };
+
+        let sealing = Sealing {
+            recipient: self.identity(),
+            ephemeral,
+            message: &contents.message,
+        };
+
+        signature
+            .verify(sender, &sealing)
+            .pot(SealedBoxError::InvalidSender, here!())?;
+
+        deserialize(&contents.message)
+    }
+
+    fn open_contents(
+        &self,
+        sealed: &[u8],
+    ) -> Result<(ExchangePublicKey, Contents), Top<SealedBoxError>> {
+        let sealed: SealedBox = bincode::deserialize(sealed)
+            .map_err(SealedBoxError::deserialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let (shared_key, role) = self.exchange_keypair().exchange(sealed.ephemeral);
+        let (_, mut receiver) = channel::channel(shared_key, role);
+
+        let contents = receiver
+            .decrypt_bytes(&sealed.ciphertext)
+            .pot(SealedBoxError::OpenFailed, here!())?;
+
+        Ok((sealed.ephemeral, deserialize(&contents)?))
+    }
+}
+
+impl Statement for Sealing<'_> {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::SealedBox;
+}
+
+fn seal<M>(
+    recipient: &KeyCard,
+    sender: Option<&KeyChain>,
+    message: &M,
+) -> Result<Vec<u8>, Top<SealedBoxError>>
+where
+    M: Serialize,
+{
+    let encryption_key = match recipient.encryption_key() {
+        Some(encryption_key) => encryption_key,
+        None => return SealedBoxError::MissingEncryptionKey.fail().spot(here!()),
+    };
+
+    let message = bincode::serialize(message)
+        .map_err(SealedBoxError::serialize_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
+
+    // A fresh `ExchangeKeyPair` is used for every sealed box, so that
+    // every sealed box is encrypted under a different key
+    let keypair = ExchangeKeyPair::random();
+    let ephemeral = keypair.public();
+
+    let signature = sender.map(|sender| {
+        let sealing = Sealing {
+            recipient: recipient.identity(),
+            ephemeral,
+            message: &message,
+        };
+
+        sender.sign(&sealing).unwrap()
+    });
+
+    let contents = bincode::serialize(&Contents { message, signature })
+        .map_err(SealedBoxError::serialize_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())?;
+
+    let (shared_key, role) = keypair.exchange(encryption_key);
+    let (mut sender, _) = channel::channel(shared_key, role);
+
+    let ciphertext = sender.encrypt_bytes(&contents);
+
+    bincode::serialize(&SealedBox {
+        ephemeral,
+        ciphertext,
+    })
+    .map_err(SealedBoxError::serialize_failed)
+    .map_err(Doom::into_top)
+    .spot(here!())
+}
+
+fn deserialize<M>(message: &[u8]) -> Result<M, Top<SealedBoxError>>
+where
+    M: DeserializeOwned,
+{
+    bincode::deserialize(message)
+        .map_err(SealedBoxError::deserialize_failed)
+        .map_err(Doom::into_top)
+        .spot(here!())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::primitives::{multi::Signer as MultiSigner, sign::Signer as SignSigner};
+
+    #[test]
+    fn correct() {
+        let alice = KeyChain::random();
+
+        let sealed = alice.keycard().seal(&42u32).unwrap();
+        let message: u32 = alice.open(&sealed).unwrap();
+
+        assert_eq!(message, 42);
+    }
+
+    #[test]
+    fn authenticated() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+        let eve = KeyChain::random();
+
+        let sealed = alice.keycard().seal_from(&bob, &42u32).unwrap();
+
+        let message: u32 = alice.open_from(&bob.keycard(), &sealed).unwrap();
+        assert_eq!(message, 42);
+
+        let message: u32 = alice.open(&sealed).unwrap();
+        assert_eq!(message, 42);
+
+        assert!(alice.open_from::<u32>(&eve.keycard(), &sealed).is_err());
+
+        let anonymous = alice.keycard().seal(&42u32).unwrap();
+        assert!(alice.open_from::<u32>(&bob.keycard(), &anonymous).is_err());
+    }
+
+    #[test]
+    fn wrong_recipient() {
+        let alice = KeyChain::random();
+        let eve = KeyChain::random();
+
+        let sealed = alice.keycard().seal(&42u32).unwrap();
+
+        assert!(eve.open::<u32>(&sealed).is_err());
+    }
+
+    #[test]
+    fn compromise_sealed() {
+        let alice = KeyChain::random();
+
+        let mut sealed = alice.keycard().seal(&42u32).unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] = sealed[last].wrapping_add(1);
+
+        assert!(alice.open::<u32>(&sealed).is_err());
+    }
+
+    #[test]
+    fn compromise_forward() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+        let carol = KeyChain::random();
+
+        // Alice cannot pass off Bob's message to her as a message from Bob to Carol
+        let sealed = alice.keycard().seal_from(&bob, &42u32).unwrap();
+        let sealed: SealedBox = bincode::deserialize(&sealed).unwrap();
+
+        let (shared_key, role) = alice.exchange_keypair().exchange(sealed.ephemeral);
+        let (_, mut receiver) = channel::channel(shared_key, role);
+        let contents: Contents = receiver.decrypt(&sealed.ciphertext).unwrap();
+
+        let keypair = ExchangeKeyPair::random();
+        let ephemeral = keypair.public();
+
+        let (shared_key, role) = keypair.exchange(carol.keycard().encryption_key().unwrap());
+        let (mut sender, _) = channel::channel(shared_key, role);
+
+        let forged = bincode::serialize(&SealedBox {
+            ephemeral,
+            ciphertext: sender.encrypt(&contents).unwrap(),
+        })
+        .unwrap();
+
+        assert_eq!(carol.open::<u32>(&forged).unwrap(), 42);
+        assert!(carol.open_from::<u32>(&bob.keycard(), &forged).is_err());
+    }
+
+    #[test]
+    fn missing_encryption_key() {
+        let alice = KeyChain::random().keycard();
+
+        let keycard = KeyCard::from_public_keys(
+            *SignSigner::public_key(&alice),
+            *MultiSigner::public_key(&alice),
+            *alice.possession(),
+        )
+        .unwrap();
+
+        assert!(keycard.encryption_key().is_none());
+        assert!(keycard.seal(&42u32).is_err());
+    }
+}
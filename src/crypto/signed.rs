@@ -0,0 +1,177 @@
+use crate::crypto::{
+    primitives::sign::{SignError, Signature as SignSignature},
+    Identity, KeyCard, KeyCardDirectory, KeyChain, Statement,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use serde::{Deserialize, Serialize};
+
+/// A `Statement` of type `S`, along with the `Identity` of its signer
+/// and the signer's signature.
+///
+/// Unlike messages authenticated by a `SecureConnection`, a `Signed`
+/// statement can be forwarded as-is (e.g., through `unicast` or
+/// `broadcast`) and verified by any third party.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signed<S> {
+    statement: S,
+    signer: Identity,
+    signature: SignSignature,
+}
+
+#[derive(Doom)]
+pub enum SignedError {
+    #[doom(description("Invalid signature"))]
+    InvalidSignature,
+    #[doom(description("`KeyCard` does not match the signer"))]
+    MismatchedSigner,
+    #[doom(description("Signer {:?} not found in directory", signer))]
+    UnknownSigner { signer: Identity },
+}
+
+impl<S> Signed<S>
+where
+    S: Statement,
+{
+    /// Signs `statement` using `keychain`.
+    ///
+    /// # Errors
+    ///
+    /// If the serialization of `statement` fails, a `SerializeFailed`
+    /// error variant will be returned.
+    pub fn new(keychain: &KeyChain, statement: S) -> Result<Self, Top<SignError>> {
+        let signature = keychain.sign(&statement)?;
+
+        Ok(Signed {
+            statement,
+            signer: keychain.identity(),
+            signature,
+        })
+    }
+
+    /// Verifies that this `Signed` statement was signed by the holder of `keycard`.
+    ///
+    /// # Errors
+    ///
+    /// If `keycard` does not belong to `signer()`, a `MismatchedSigner` error
+    /// variant will be returned. If the signature is invalid, `InvalidSignature`
+    /// will be returned.
+    pub fn verify(&self, keycard: &KeyCard) -> Result<(), Top<SignedError>> {
+        if keycard.identity() != self.signer {
+            return SignedError::MismatchedSigner.fail().spot(here!());
+        }
+
+        self.signature
+            .verify(keycard, &self.statement)
+            .pot(SignedError::InvalidSignature, here!())
+    }
+
+    /// Verifies this `Signed` statement against the `KeyCard` of
+    /// `signer()` in `directory`.
+    ///
+    /// # Errors
+    ///
+    /// If `directory` has no `KeyCard` for `signer()`, an `UnknownSigner`
+    /// error variant will be returned.
+    pub fn verify_in<D>(&self, directory: &D) -> Result<(), Top<SignedError>>
+    where
+        D: KeyCardDirectory + ?Sized,
+    {
+        match directory.keycard(self.signer) {
+            Some(keycard) => self.verify(keycard),
+            None => SignedError::UnknownSigner {
+                signer: self.signer,
+            }
+            .fail()
+            .spot(here!()),
+        }
+    }
+}
+
+impl<S> Signed<S> {
+    pub fn statement(&self) -> &S {
+        &self.statement
+    }
+
+    pub fn signer(&self) -> Identity {
+        self.signer
+    }
+
+    pub fn signature(&self) -> &SignSignature {
+        &self.signature
+    }
+
+    /// Returns the `Statement` of this `Signed`, discarding its signature.
+    pub fn into_statement(self) -> S {
+        self.statement
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Committee;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct TestStatement(u64);
+
+    impl Statement for TestStatement {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    #[test]
+    fn correct() {
+        let alice = KeyChain::random();
+
+        let signed = Signed::new(&alice, TestStatement(42)).unwrap();
+
+        assert_eq!(signed.signer(), alice.keycard().identity());
+        signed.verify(&alice.keycard()).unwrap();
+
+        let bytes = bincode::serialize(&signed).unwrap();
+        let signed: Signed<TestStatement> = bincode::deserialize(&bytes).unwrap();
+
+        signed.verify(&alice.keycard()).unwrap();
+        assert_eq!(signed.into_statement(), TestStatement(42));
+    }
+
+    #[test]
+    fn directory() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let signed = Signed::new(&alice, TestStatement(42)).unwrap();
+
+        let directory = vec![alice.keycard(), bob.keycard()]
+            .into_iter()
+            .map(|keycard| (keycard.identity(), keycard))
+            .collect::<HashMap<_, _>>();
+
+        signed.verify_in(&directory).unwrap();
+
+        let committee = Committee::new(vec![alice.keycard(), bob.keycard()]);
+        signed.verify_in(&committee).unwrap();
+
+        let committee = Committee::new(vec![bob.keycard()]);
+        assert!(signed.verify_in(&committee).is_err());
+    }
+
+    #[test]
+    fn compromise() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+
+        let signed = Signed::new(&alice, TestStatement(42)).unwrap();
+
+        assert!(signed.verify(&bob.keycard()).is_err());
+
+        let mut forged = signed.clone();
+        forged.signer = bob.identity();
+        assert!(forged.verify(&bob.keycard()).is_err());
+
+        let mut forged = signed;
+        forged.statement = TestStatement(43);
+        assert!(forged.verify(&alice.keycard()).is_err());
+    }
+}
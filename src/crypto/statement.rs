@@ -7,3 +7,49 @@ pub trait Statement: Serialize {
     type Header: Serialize;
     const HEADER: Self::Header;
 }
+
+// Implemented by `#[derive(Statement)]` on header enums, once per claimed
+// variant: two `Statement`s claiming the same variant fail to compile.
+#[doc(hidden)]
+pub trait HeaderClaim<const DISCRIMINANT: usize> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::{KeyChain, Scope, Statement, StatementRegistry};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    enum Header {
+        Vote,
+        Commit,
+    }
+
+    #[derive(Serialize, Statement)]
+    #[statement(scope = Scope::application("consensus"), header = Header::Vote)]
+    struct Vote(u64);
+
+    #[derive(Serialize, Statement)]
+    #[statement(scope = Scope::application("consensus"), header = Header::Commit)]
+    struct Commit(u64);
+
+    #[derive(Serialize, Statement)]
+    struct Plain(u64);
+
+    #[test]
+    fn derive() {
+        assert_eq!(Vote::SCOPE, Scope::application("consensus"));
+        assert_eq!(Plain::SCOPE, Scope::user());
+
+        let mut registry = StatementRegistry::new();
+
+        registry.register::<Vote>().unwrap();
+        registry.register::<Commit>().unwrap();
+        registry.register::<Plain>().unwrap();
+
+        let keychain = KeyChain::random();
+        let signature = keychain.sign(&Vote(42)).unwrap();
+
+        signature.verify(&keychain.keycard(), &Vote(42)).unwrap();
+        assert!(signature.verify(&keychain.keycard(), &Commit(42)).is_err());
+    }
+}
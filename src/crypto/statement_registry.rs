@@ -0,0 +1,161 @@
+use crate::crypto::Statement;
+use doomstack::{here, Doom, ResultExt, Top};
+use std::{any, collections::HashMap};
+
+/// A set of `Statement` types, indexed by scope and header.
+///
+/// Two `Statement` types that share both `SCOPE` and `HEADER` can be
+/// used to replay each other's signatures. Registering every `Statement`
+/// of an application at startup detects such collisions early.
+#[derive(Debug, Default)]
+pub struct StatementRegistry {
+    statements: HashMap<Vec<u8>, &'static str>,
+}
+
+#[derive(Doom)]
+pub enum StatementRegistryError {
+    #[doom(description(
+        "Statements `{}` and `{}` share scope and header",
+        registered,
+        colliding
+    ))]
+    HeaderCollision {
+        registered: &'static str,
+        colliding: &'static str,
+    },
+    #[doom(description("Failed to serialize: {}", source))]
+    #[doom(wrap(serialize_failed))]
+    SerializeFailed { source: bincode::Error },
+}
+
+impl StatementRegistry {
+    pub fn new() -> Self {
+        StatementRegistry::default()
+    }
+
+    /// Registers `S`.
+    ///
+    /// Registering the same type more than once has no effect.
+    ///
+    /// # Errors
+    ///
+    /// If a different type with the same scope and header as `S` was already
+    /// registered, a `HeaderCollision` error variant will be returned.
+    pub fn register<S>(&mut self) -> Result<(), Top<StatementRegistryError>>
+    where
+        S: Statement,
+    {
+        let key = bincode::serialize(&(S::SCOPE, S::HEADER))
+            .map_err(StatementRegistryError::serialize_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let name = any::type_name::<S>();
+
+        match self.statements.get(&key).copied() {
+            Some(registered) if registered != name => StatementRegistryError::HeaderCollision {
+                registered,
+                colliding: name,
+            }
+            .fail()
+            .spot(here!()),
+            _ => {
+                self.statements.insert(key, name);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{KeyChain, Scope};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    enum Header {
+        Vote,
+        Commit,
+    }
+
+    #[derive(Serialize)]
+    struct Vote(u64);
+
+    impl Statement for Vote {
+        const SCOPE: Scope = Scope::application("consensus");
+        type Header = Header;
+        const HEADER: Header = Header::Vote;
+    }
+
+    #[derive(Serialize)]
+    struct Commit(u64);
+
+    impl Statement for Commit {
+        const SCOPE: Scope = Scope::application("consensus");
+        type Header = Header;
+        const HEADER: Header = Header::Commit;
+    }
+
+    #[derive(Serialize)]
+    struct OtherVote(u64);
+
+    impl Statement for OtherVote {
+        const SCOPE: Scope = Scope::application("storage");
+        type Header = Header;
+        const HEADER: Header = Header::Vote;
+    }
+
+    #[derive(Serialize)]
+    struct CollidingVote(u64);
+
+    impl Statement for CollidingVote {
+        const SCOPE: Scope = Scope::application("consensus");
+        type Header = Header;
+        const HEADER: Header = Header::Vote;
+    }
+
+    #[test]
+    fn distinct() {
+        let mut registry = StatementRegistry::new();
+
+        registry.register::<Vote>().unwrap();
+        registry.register::<Commit>().unwrap();
+        registry.register::<OtherVote>().unwrap();
+        registry.register::<Vote>().unwrap();
+
+        assert_eq!(registry.len(), 3);
+    }
+
+    #[test]
+    fn collision() {
+        let mut registry = StatementRegistry::new();
+
+        registry.register::<Vote>().unwrap();
+        assert!(registry.register::<CollidingVote>().is_err());
+    }
+
+    #[test]
+    fn scope_separation() {
+        let keychain = KeyChain::random();
+        let keycard = keychain.keycard();
+
+        let signature = keychain.sign(&Vote(42)).unwrap();
+
+        signature.verify(&keycard, &Vote(42)).unwrap();
+        assert!(signature.verify(&keycard, &OtherVote(42)).is_err());
+
+        let multisignature = keychain.multisign(&Vote(42)).unwrap();
+
+        multisignature.verify([&keycard], &Vote(42)).unwrap();
+        assert!(multisignature.verify([&keycard], &OtherVote(42)).is_err());
+    }
+}
@@ -4,4 +4,13 @@ use serde::{Deserialize, Serialize};
 #[repr(i8)]
 pub(crate) enum TalkHeader {
     SecureConnectionIdentityChallenge = 0,
+    // Retired: proofs of possession are signed under a dedicated ciphersuite
+    #[allow(dead_code)]
+    KeyCardPossession = 1,
+    KeyRotation = 2,
+    KeyCardEncryption = 3,
+    SealedBox = 4,
+    PeerRecord = 5,
+    SecureConnectionHandshake = 6,
+    KeyRotationAcceptance = 7,
 }
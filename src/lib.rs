@@ -1,3 +1,6 @@
+// Allows `talk-derive` to refer to `::talk` from within this crate
+extern crate self as talk;
+
 pub mod broadcast;
 pub mod crypto;
 pub mod link;
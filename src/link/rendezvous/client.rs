@@ -1,5 +1,5 @@
 use crate::{
-    crypto::{Identity, KeyCard},
+    crypto::{Identity, KeyCard, Lineage, PeerRecord, Succession},
     link::rendezvous::{ClientSettings, Request, Response, ShardId},
     net::traits::TcpConnect,
 };
@@ -19,12 +19,26 @@ pub enum ClientError {
     AlreadyPublished { shard: Option<ShardId> },
     #[doom(description("Card unknown"))]
     CardUnknown,
+    #[doom(description("Lineage is full"))]
+    LineageFull,
+    #[doom(description("Record is expired"))]
+    RecordExpired,
+    #[doom(description("Record is superseded by a published record"))]
+    RecordStale,
+    #[doom(description("Record unknown"))]
+    RecordUnknown,
     #[doom(description("Shard is full"))]
     ShardFull,
     #[doom(description("Shard ID is invalid"))]
     ShardIdInvalid,
     #[doom(description("Shard is incomplete"))]
     ShardIncomplete,
+    #[doom(description("Succession is invalid"))]
+    SuccessionInvalid,
+    #[doom(description("Lineage does not contain the requested identity"))]
+    UnexpectedLineage,
+    #[doom(description("Record does not belong to the requested identity"))]
+    UnexpectedRecord,
 }
 
 #[derive(Doom)]
@@ -65,6 +79,28 @@ impl Client {
         }
     }
 
+    /// Replaces the `KeyCard` rotated out by `succession` with its successor.
+    ///
+    /// The successor inherits the shard and the address of its predecessor.
+    /// The predecessor's `Identity` keeps resolving to the successor in
+    /// `get_address` and `get_lineage`. A `Lineage` can only be extended up
+    /// to the server's `max_lineage_length`: beyond it, a `LineageFull`
+    /// error variant will be returned.
+    pub async fn publish_succession(&self, succession: Succession) -> Result<(), Top<ClientError>> {
+        match self.perform(&Request::PublishSuccession(succession)).await {
+            Response::AcknowledgeSuccession => Ok(()),
+            Response::CardUnknown => ClientError::CardUnknown.fail().spot(here!()),
+            Response::SuccessionInvalid => ClientError::SuccessionInvalid.fail().spot(here!()),
+            Response::LineageFull => ClientError::LineageFull.fail().spot(here!()),
+            response => {
+                panic!(
+                    "unexpected response to `publish_succession`: {:?}",
+                    response
+                )
+            }
+        }
+    }
+
     pub async fn advertise_port(&self, identity: Identity, port: u16) {
         match self.perform(&Request::AdvertisePort(identity, port)).await {
             Response::AcknowledgePort => (),
@@ -72,6 +108,19 @@ impl Client {
         }
     }
 
+    /// Publishes `record`, replacing any `PeerRecord` with a lower
+    /// `sequence` number previously published for the same `Identity`.
+    pub async fn publish_record(&self, record: PeerRecord) -> Result<(), Top<ClientError>> {
+        match self.perform(&Request::PublishRecord(record)).await {
+            Response::AcknowledgeRecord => Ok(()),
+            Response::RecordExpired => ClientError::RecordExpired.fail().spot(here!()),
+            Response::RecordStale => ClientError::RecordStale.fail().spot(here!()),
+            response => {
+                panic!("unexpected response to `publish_record`: {:?}", response)
+            }
+        }
+    }
+
     pub async fn get_shard(&self, shard: ShardId) -> Result<Vec<KeyCard>, Top<ClientError>> {
         match self.perform(&Request::GetShard(shard)).await {
             Response::Shard(shard) => Ok(shard),
@@ -83,6 +132,9 @@ impl Client {
         }
     }
 
+    /// Retrieves the `KeyCard` of `identity`. If `identity` was rotated
+    /// out, the `KeyCard` of its latest successor is returned instead: use
+    /// `get_lineage` to verify the succession.
     pub async fn get_card(&self, identity: Identity) -> Result<KeyCard, Top<ClientError>> {
         match self.perform(&Request::GetCard(identity)).await {
             Response::Card(card) => Ok(card),
@@ -103,6 +155,39 @@ impl Client {
         }
     }
 
+    /// Retrieves the `Lineage` of `identity`, whose `current` `KeyCard`
+    /// is the latest successor of `identity`.
+    pub async fn get_lineage(&self, identity: Identity) -> Result<Lineage, Top<ClientError>> {
+        match self.perform(&Request::GetLineage(identity)).await {
+            Response::Lineage(lineage) if lineage.contains(identity) => Ok(lineage),
+            Response::Lineage(_) => ClientError::UnexpectedLineage.fail().spot(here!()),
+            Response::CardUnknown => ClientError::CardUnknown.fail().spot(here!()),
+            response => {
+                panic!("unexpected response to `get_lineage`: {:?}", response)
+            }
+        }
+    }
+
+    /// Retrieves the latest unexpired `PeerRecord` published for `identity`.
+    ///
+    /// `PeerRecord`s are self-signed: the record returned by the
+    /// `Server` is verified upon reception.
+    pub async fn get_record(&self, identity: Identity) -> Result<PeerRecord, Top<ClientError>> {
+        match self.perform(&Request::GetRecord(identity)).await {
+            Response::Record(record) if record.identity() != identity => {
+                ClientError::UnexpectedRecord.fail().spot(here!())
+            }
+            Response::Record(record) if record.is_expired() => {
+                ClientError::RecordExpired.fail().spot(here!())
+            }
+            Response::Record(record) => Ok(record),
+            Response::RecordUnknown => ClientError::RecordUnknown.fail().spot(here!()),
+            response => {
+                panic!("unexpected response to `get_record`: {:?}", response)
+            }
+        }
+    }
+
     async fn perform(&self, request: &Request) -> Response {
         let mut sleep_agent = self.settings.sleep_schedule.agent();
 
@@ -142,16 +227,25 @@ impl Client {
 mod tests {
     use super::*;
     use crate::{
-        crypto::KeyChain,
+        crypto::{KeyChain, PeerRecord},
         link::rendezvous::{Server, ServerSettings},
     };
-    use std::time::Duration;
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, SystemTime},
+    };
     use tokio::time;
 
     async fn setup_server(address: &'static str, shard_sizes: Vec<usize>) -> Server {
-        Server::new(address, ServerSettings { shard_sizes })
-            .await
-            .unwrap()
+        Server::new(
+            address,
+            ServerSettings {
+                shard_sizes,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
     }
 
     async fn setup_clients(
@@ -377,6 +471,72 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn succession() {
+        let (_server, keychains, keycards, identities, clients) =
+            setup("127.0.0.1:1240", 2, vec![2]).await;
+
+        for j in 0..2 {
+            clients[j]
+                .publish_card(keycards[j].clone(), Some(0))
+                .await
+                .unwrap();
+        }
+
+        let rotated = KeyChain::random();
+        let succession = keychains[0].rotate(&rotated);
+
+        clients[0].publish_succession(succession).await.unwrap();
+
+        let lineage = clients[1].get_lineage(identities[0]).await.unwrap();
+
+        assert_eq!(lineage.genesis(), &keycards[0]);
+        assert_eq!(lineage.current(), &rotated.keycard());
+
+        assert_eq!(
+            clients[1].get_card(identities[0]).await.unwrap(),
+            rotated.keycard()
+        );
+
+        let shard = clients[1].get_shard(0).await.unwrap();
+
+        assert!(shard.contains(&rotated.keycard()));
+        assert!(shard.contains(&keycards[1]));
+        assert!(!shard.contains(&keycards[0]));
+
+        // A `KeyCard` can only be rotated out by its own `KeyChain`
+        let succession = keychains[0].rotate(&KeyChain::random());
+
+        match clients[0]
+            .publish_succession(succession)
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::CardUnknown => (),
+            error => panic!(
+                "unexpected error upon publishing stale succession: {}",
+                error
+            ),
+        }
+
+        // A `KeyCard` cannot be rotated into an already published `KeyCard`
+        let succession = keychains[1].rotate(&rotated);
+
+        match clients[1]
+            .publish_succession(succession)
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::SuccessionInvalid => (),
+            error => panic!(
+                "unexpected error upon publishing colliding succession: {}",
+                error
+            ),
+        }
+    }
+
     #[tokio::test]
     async fn invalid_shard() {
         let (_server, _keychains, keycards, _identities, clients) =
@@ -395,4 +555,145 @@ mod tests {
             ),
         }
     }
+
+    #[tokio::test]
+    async fn records() {
+        let (_server, keychains, _keycards, identities, clients) =
+            setup("127.0.0.1:1241", 2, vec![2]).await;
+
+        let record = |sequence| {
+            PeerRecord::new(
+                &keychains[0],
+                sequence,
+                vec!["127.0.0.1:9000".parse().unwrap()],
+                BTreeMap::new(),
+                SystemTime::now() + Duration::from_secs(3600),
+            )
+        };
+
+        match clients[1]
+            .get_record(identities[0])
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::RecordUnknown => (),
+            error => panic!("unexpected error upon querying record: {}", error),
+        }
+
+        clients[0].publish_record(record(1)).await.unwrap();
+
+        let fetched = clients[1].get_record(identities[0]).await.unwrap();
+        assert_eq!(fetched.sequence(), 1);
+        assert_eq!(fetched.keycard(), &keychains[0].keycard());
+
+        match clients[0]
+            .publish_record(record(0))
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::RecordStale => (),
+            error => panic!("unexpected error upon publishing stale record: {}", error),
+        }
+
+        clients[0].publish_record(record(2)).await.unwrap();
+
+        let fetched = clients[1].get_record(identities[0]).await.unwrap();
+        assert_eq!(fetched.sequence(), 2);
+    }
+
+    #[tokio::test]
+    async fn records_bounded() {
+        let _server = Server::new(
+            "127.0.0.1:1242",
+            ServerSettings {
+                max_records: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let (keychains, _, identities, clients) = setup_clients("127.0.0.1:1242", 2).await;
+
+        let record = |keychain, lifetime| {
+            PeerRecord::new(
+                keychain,
+                0,
+                vec!["127.0.0.1:9000".parse().unwrap()],
+                BTreeMap::new(),
+                SystemTime::now() + lifetime,
+            )
+        };
+
+        clients[0]
+            .publish_record(record(&keychains[0], Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        // The soonest-expiring record is evicted to make room
+        clients[1]
+            .publish_record(record(&keychains[1], Duration::from_secs(7200)))
+            .await
+            .unwrap();
+
+        match clients[1]
+            .get_record(identities[0])
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::RecordUnknown => (),
+            error => panic!("unexpected error upon querying evicted record: {}", error),
+        }
+
+        clients[0].get_record(identities[1]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lineage_bounded() {
+        let _server = Server::new(
+            "127.0.0.1:1243",
+            ServerSettings {
+                max_lineage_length: 3,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let (keychains, keycards, identities, clients) = setup_clients("127.0.0.1:1243", 1).await;
+
+        clients[0]
+            .publish_card(keycards[0].clone(), None)
+            .await
+            .unwrap();
+
+        let mut current = keychains[0].clone();
+
+        for _ in 0..2 {
+            let next = KeyChain::random();
+
+            clients[0]
+                .publish_succession(current.rotate(&next))
+                .await
+                .unwrap();
+
+            current = next;
+        }
+
+        match clients[0]
+            .publish_succession(current.rotate(&KeyChain::random()))
+            .await
+            .unwrap_err()
+            .top()
+        {
+            ClientError::LineageFull => (),
+            error => panic!("unexpected error upon extending full lineage: {}", error),
+        }
+
+        let lineage = clients[0].get_lineage(identities[0]).await.unwrap();
+        assert_eq!(lineage.current(), &current.keycard());
+    }
 }
@@ -1,34 +1,47 @@
 use crate::{
     crypto::{Identity, KeyChain},
-    link::rendezvous::{Client, ConnectorSettings},
-    net::{traits::TcpConnect, Connector as NetConnector, SecureConnection},
+    link::rendezvous::{Client, ConnectorSettings, Opening},
+    net::{
+        traits::TcpConnect, Connector as NetConnector, PlainConnection, SecureConnection, Ticket,
+        TicketStore,
+    },
 };
 use async_trait::async_trait;
 use doomstack::{here, Doom, ResultExt, Stack, Top};
 use parking_lot::Mutex;
 use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
 
+/// Connects to rendezvous `Listener`s.
+///
+/// Every connection opens with a short, versioned preamble, which
+/// `Listener`s of earlier versions of `talk` do not understand: connecting
+/// to them fails with a `HandshakeFailed` error variant.
 pub struct Connector {
     client: Client,
     keychain: KeyChain,
+    request_tickets: bool,
     database: Arc<Mutex<Database>>,
 }
 
 struct Database {
     cache: HashMap<Identity, SocketAddr>,
+    successors: HashMap<Identity, Identity>,
+    tickets: TicketStore,
 }
 
 #[derive(Doom)]
 pub enum ConnectorError {
     #[doom(description("Address unknown"))]
     AddressUnknown,
-    #[doom(description("Failed to `authenticate` connection"))]
-    AuthenticateFailed,
     #[doom(description("Failed to connect: {}", source))]
     #[doom(wrap(connect_failed))]
     ConnectFailed { source: io::Error },
-    #[doom(description("Failed to `secure` connection"))]
-    SecureFailed,
+    #[doom(description("Failed to complete handshake"))]
+    HandshakeFailed,
+    #[doom(description("Failed to resume session"))]
+    ResumeFailed,
+    #[doom(description("Failed to receive resumption ticket"))]
+    TicketFailed,
     #[doom(description("Unexpected remote: {:?}", remote))]
     UnexpectedRemote { remote: Identity },
 }
@@ -40,13 +53,19 @@ impl Connector {
     {
         let client = Client::new(server, settings.client_settings);
 
+        // A `TicketStore` with no capacity would discard every ticket
+        let request_tickets = settings.ticket_store_settings.capacity > 0;
+
         let database = Arc::new(Mutex::new(Database {
             cache: HashMap::new(),
+            successors: HashMap::new(),
+            tickets: TicketStore::new(settings.ticket_store_settings),
         }));
 
         Connector {
             client,
             keychain,
+            request_tickets,
             database,
         }
     }
@@ -57,22 +76,61 @@ impl Connector {
             .ok_or(ConnectorError::AddressUnknown.into_top())
             .spot(here!())?;
 
-        let mut connection = address
-            .connect()
-            .await
-            .map_err(ConnectorError::connect_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())?
-            .secure()
+        let ticket = self.database.lock().take_ticket(identity);
+
+        if let Some(ticket) = ticket {
+            // If `ticket` is rejected (e.g., because the remote restarted),
+            // it is discarded and a full handshake is run instead
+            if let Ok(connection) = self.resume(address, ticket).await {
+                return Ok(connection);
+            }
+        }
+
+        self.handshake(identity, address).await
+    }
+
+    async fn resume(
+        &self,
+        address: SocketAddr,
+        ticket: Ticket,
+    ) -> Result<SecureConnection, Top<ConnectorError>> {
+        let mut connection = self
+            .open(
+                address,
+                Opening::Resume {
+                    request_ticket: self.request_tickets,
+                },
+            )
+            .await?
+            .resume(&ticket)
             .await
-            .pot(ConnectorError::SecureFailed, here!())?;
+            .pot(ConnectorError::ResumeFailed, here!())?;
+
+        self.renew_ticket(&mut connection).await?;
 
-        let keycard = connection
-            .authenticate(&self.keychain)
+        Ok(connection)
+    }
+
+    async fn handshake(
+        &self,
+        identity: Identity,
+        address: SocketAddr,
+    ) -> Result<SecureConnection, Top<ConnectorError>> {
+        let (mut connection, keycard) = self
+            .open(
+                address,
+                Opening::Handshake {
+                    request_ticket: self.request_tickets,
+                },
+            )
+            .await?
+            .initiate_handshake(&self.keychain)
             .await
-            .pot(ConnectorError::AuthenticateFailed, here!())?;
+            .pot(ConnectorError::HandshakeFailed, here!())?;
+
+        if keycard.identity() == identity || self.succeeds(identity, keycard.identity()).await {
+            self.renew_ticket(&mut connection).await?;
 
-        if keycard.identity() == identity {
             Ok(connection)
         } else {
             ConnectorError::UnexpectedRemote {
@@ -83,6 +141,67 @@ impl Connector {
         }
     }
 
+    async fn open(
+        &self,
+        address: SocketAddr,
+        opening: Opening,
+    ) -> Result<PlainConnection, Top<ConnectorError>> {
+        let mut connection = address
+            .connect()
+            .await
+            .map_err(ConnectorError::connect_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        connection
+            .send_bytes(&opening.to_bytes())
+            .await
+            .pot(ConnectorError::HandshakeFailed, here!())?;
+
+        Ok(connection)
+    }
+
+    async fn renew_ticket(
+        &self,
+        connection: &mut SecureConnection,
+    ) -> Result<(), Top<ConnectorError>> {
+        if self.request_tickets {
+            let ticket = connection
+                .receive_ticket()
+                .await
+                .pot(ConnectorError::TicketFailed, here!())?;
+
+            self.database.lock().tickets.insert(ticket);
+        }
+
+        Ok(())
+    }
+
+    // Determines whether `remote` is the current successor of `identity`
+    async fn succeeds(&self, identity: Identity, remote: Identity) -> bool {
+        if self.database.lock().successors.get(&identity) == Some(&remote) {
+            return true;
+        }
+
+        let lineage = match self.client.get_lineage(identity).await {
+            Ok(lineage) => lineage,
+            Err(_) => return false,
+        };
+
+        let current = lineage.current().identity();
+
+        let mut database = self.database.lock();
+
+        for historical in lineage
+            .identities()
+            .filter(|historical| *historical != current)
+        {
+            database.successors.insert(historical, current);
+        }
+
+        current == remote
+    }
+
     async fn refresh(&self, identity: Identity) -> bool {
         let stale = self.get_address(identity);
         let fresh = self
@@ -109,6 +228,15 @@ impl Connector {
     }
 }
 
+impl Database {
+    // `Ticket`s are stored under the `Identity` authenticated by the
+    // handshake, i.e., the current successor of `identity`, if known
+    fn take_ticket(&mut self, identity: Identity) -> Option<Ticket> {
+        let current = self.successors.get(&identity).copied().unwrap_or(identity);
+        self.tickets.take(current)
+    }
+}
+
 #[async_trait]
 impl NetConnector for Connector {
     async fn connect(&self, identity: Identity) -> Result<SecureConnection, Stack> {
@@ -160,4 +288,93 @@ mod tests {
 
         alice_task.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn connect_resumed() {
+        const SERVER: &str = "127.0.0.1:1252";
+        const MESSAGE: &str = "Hello Alice, this is Bob!";
+
+        let _server = Server::new(SERVER, Default::default()).await.unwrap();
+
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let alice_identity = alice_keychain.keycard().identity();
+        let bob_identity = bob_keychain.keycard().identity();
+
+        let mut alice_listener = Listener::new(SERVER, alice_keychain, Default::default()).await;
+
+        let bob_connector = Connector::new(SERVER, bob_keychain, Default::default());
+
+        let alice_task = tokio::spawn(async move {
+            for _ in 0..3 {
+                let (remote, mut connection) = alice_listener.accept().await.unwrap();
+
+                assert_eq!(remote, bob_identity);
+                assert_eq!(connection.receive::<String>().await.unwrap(), MESSAGE);
+            }
+        });
+
+        for _ in 0..3 {
+            let mut connection = bob_connector.connect(alice_identity).await.unwrap();
+            connection.send(&String::from(MESSAGE)).await.unwrap();
+
+            // Every connection leaves a fresh ticket behind
+            assert_eq!(bob_connector.database.lock().tickets.len(), 1);
+        }
+
+        alice_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn connect_rotated() {
+        const SERVER: &str = "127.0.0.1:1251";
+        const MESSAGE: &str = "Hello Alice, this is Bob!";
+
+        let _server = Server::new(SERVER, Default::default()).await.unwrap();
+
+        let alice_keychain = KeyChain::random();
+        let alice_rotated_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let alice_identity = alice_keychain.keycard().identity();
+        let bob_identity = bob_keychain.keycard().identity();
+
+        let client = Client::new(SERVER, Default::default());
+
+        client
+            .publish_card(alice_keychain.keycard(), None)
+            .await
+            .unwrap();
+
+        client
+            .publish_succession(alice_keychain.rotate(&alice_rotated_keychain))
+            .await
+            .unwrap();
+
+        let mut alice_listener =
+            Listener::new(SERVER, alice_rotated_keychain, Default::default()).await;
+
+        let bob_connector = Connector::new(SERVER, bob_keychain, Default::default());
+
+        let alice_task = tokio::spawn(async move {
+            let (remote, mut connection) = alice_listener.accept().await.unwrap();
+
+            assert_eq!(remote, bob_identity);
+            assert_eq!(connection.receive::<String>().await.unwrap(), MESSAGE);
+        });
+
+        let mut connection = bob_connector.connect(alice_identity).await.unwrap();
+
+        connection.send(&String::from(MESSAGE)).await.unwrap();
+
+        alice_task.await.unwrap();
+
+        // The ticket issued by Alice's successor is found under Alice's `Identity`
+        assert!(bob_connector
+            .database
+            .lock()
+            .take_ticket(alice_identity)
+            .is_some());
+    }
 }
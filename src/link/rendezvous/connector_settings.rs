@@ -1,6 +1,7 @@
-use crate::link::rendezvous::ClientSettings;
+use crate::{link::rendezvous::ClientSettings, net::TicketStoreSettings};
 
 #[derive(Debug, Clone, Default)]
 pub struct ConnectorSettings {
     pub client_settings: ClientSettings,
+    pub ticket_store_settings: TicketStoreSettings,
 }
@@ -1,12 +1,15 @@
 use crate::{
     crypto::{Identity, KeyChain},
-    link::rendezvous::{Client, ListenerSettings},
-    net::{traits::TcpConnect, Listener as NetListener, PlainConnection, SecureConnection},
+    link::rendezvous::{Client, ListenerSettings, Opening},
+    net::{
+        traits::TcpConnect, ConnectionSettings, Listener as NetListener, PlainConnection,
+        SecureConnection, TicketKey,
+    },
     sync::fuse::Fuse,
 };
 use async_trait::async_trait;
 use doomstack::{here, Doom, ResultExt, Stack, Top};
-use std::net::Ipv4Addr;
+use std::{net::Ipv4Addr, sync::Arc, time::Duration};
 use tokio::{
     net::TcpListener,
     sync::{
@@ -15,8 +18,16 @@ use tokio::{
     },
 };
 
+const OPENING_MAX_MESSAGE_SIZE: usize = 1 << 8;
+
 type Outlet = Receiver<(Identity, SecureConnection)>;
 
+/// Accepts connections from rendezvous `Connector`s.
+///
+/// `Connector`s open every connection with a short, versioned preamble.
+/// This is a breaking change to the wire format: connections from
+/// `Connector`s of earlier versions of `talk`, which start right away
+/// with a handshake, are rejected.
 pub struct Listener {
     outlet: Outlet,
     _fuse: Fuse,
@@ -24,10 +35,14 @@ pub struct Listener {
 
 #[derive(Doom)]
 enum ServeError {
-    #[doom(description("Failed to `secure` the connection"))]
-    SecureFailed,
-    #[doom(description("Failed to `authenticate` the connection"))]
-    AuthenticateFailed,
+    #[doom(description("Failed to complete the handshake"))]
+    HandshakeFailed,
+    #[doom(description("Remote speaks an incompatible protocol version"))]
+    IncompatibleRemote,
+    #[doom(description("Failed to resume the session"))]
+    ResumeFailed,
+    #[doom(description("Failed to issue a resumption ticket"))]
+    TicketFailed,
 }
 
 impl Listener {
@@ -48,8 +63,10 @@ impl Listener {
 
         let (inlet, outlet) = mpsc::channel(settings.channel_capacity);
 
+        let ticket_lifetime = settings.ticket_lifetime;
+
         fuse.spawn(async move {
-            let _ = Listener::listen(keychain, listener, inlet).await;
+            let _ = Listener::listen(keychain, ticket_lifetime, listener, inlet).await;
         });
 
         let client = Client::new(server, settings.client_settings);
@@ -63,11 +80,15 @@ impl Listener {
 
     async fn listen(
         keychain: KeyChain,
+        ticket_lifetime: Duration,
         listener: TcpListener,
         inlet: Sender<(Identity, SecureConnection)>,
     ) {
         let fuse = Fuse::new();
 
+        // Tickets issued by this `Listener` are only valid for its lifetime
+        let ticket_key = Arc::new(TicketKey::random());
+
         loop {
             if let Ok((stream, _)) = listener.accept().await.and_then(|(stream, addr)| {
                 stream.set_nodelay(true)?;
@@ -76,33 +97,71 @@ impl Listener {
                 let connection = stream.into();
 
                 let keychain = keychain.clone();
+                let ticket_key = ticket_key.clone();
                 let inlet = inlet.clone();
 
                 fuse.spawn(async move {
-                    let _ = Listener::serve(connection, keychain, inlet).await;
+                    let _ =
+                        Listener::serve(connection, keychain, ticket_key, ticket_lifetime, inlet)
+                            .await;
                 });
             }
         }
     }
 
     async fn serve(
-        connection: PlainConnection,
+        mut connection: PlainConnection,
         keychain: KeyChain,
+        ticket_key: Arc<TicketKey>,
+        ticket_lifetime: Duration,
         inlet: Sender<(Identity, SecureConnection)>,
     ) -> Result<(), Top<ServeError>> {
-        let mut connection = connection
-            .secure()
-            .await
-            .pot(ServeError::SecureFailed, here!())?;
+        // `Opening`s are tiny, and sent by unauthenticated remotes
+        connection.configure(ConnectionSettings {
+            max_message_size: OPENING_MAX_MESSAGE_SIZE,
+            ..Default::default()
+        });
 
-        let keycard = connection
-            .authenticate(&keychain)
+        let opening = connection
+            .receive_bytes()
             .await
-            .pot(ServeError::AuthenticateFailed, here!())?;
+            .pot(ServeError::HandshakeFailed, here!())?;
+
+        let opening = Opening::from_bytes(&opening)
+            .ok_or(ServeError::IncompatibleRemote.into_top())
+            .spot(here!())?;
+
+        connection.configure(Default::default());
+
+        let (mut connection, identity, request_ticket) = match opening {
+            Opening::Handshake { request_ticket } => {
+                let (connection, keycard) = connection
+                    .accept_handshake(&keychain)
+                    .await
+                    .pot(ServeError::HandshakeFailed, here!())?;
+
+                (connection, keycard.identity(), request_ticket)
+            }
+            Opening::Resume { request_ticket } => {
+                let (connection, identity) = connection
+                    .accept_resumption(ticket_key.as_ref())
+                    .await
+                    .pot(ServeError::ResumeFailed, here!())?;
+
+                (connection, identity, request_ticket)
+            }
+        };
+
+        if request_ticket {
+            connection
+                .issue_ticket(ticket_key.as_ref(), ticket_lifetime)
+                .await
+                .pot(ServeError::TicketFailed, here!())?;
+        }
 
         // This can only fail if the (local) receiving end is
         // dropped, in which case we don't care about the error
-        let _ = inlet.try_send((keycard.identity(), connection));
+        let _ = inlet.try_send((identity, connection));
 
         Ok(())
     }
@@ -1,9 +1,11 @@
 use crate::link::rendezvous::ClientSettings;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct ListenerSettings {
     pub client_settings: ClientSettings,
     pub channel_capacity: usize,
+    pub ticket_lifetime: Duration,
 }
 
 impl Default for ListenerSettings {
@@ -11,6 +13,7 @@ impl Default for ListenerSettings {
         ListenerSettings {
             client_settings: Default::default(),
             channel_capacity: 32,
+            ticket_lifetime: Duration::from_secs(3600),
         }
     }
 }
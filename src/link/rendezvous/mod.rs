@@ -4,12 +4,14 @@ mod connector;
 mod connector_settings;
 mod listener;
 mod listener_settings;
+mod opening;
 mod request;
 mod response;
 mod server;
 mod server_settings;
 mod shard_id;
 
+use opening::Opening;
 use request::Request;
 use response::Response;
 
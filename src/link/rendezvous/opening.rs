@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+// Every `Opening` is prefixed with `OPENING_MAGIC` and `OPENING_VERSION`.
+// `Connector`s and `Listener`s that predate `Opening`s start right away
+// with a handshake, and cannot interoperate with current ones: `Listener`s
+// detect them (as well as future, incompatible versions) by the prefix.
+const OPENING_MAGIC: [u8; 8] = *b"talkopen";
+const OPENING_VERSION: u8 = 1;
+
+// Sent by a `Connector` to a `Listener`, in the clear, before securing a
+// connection: `Resume` is followed by a resumption, `Handshake` by a full
+// handshake. If `request_ticket` is set, the `Listener` issues a new
+// resumption ticket once the connection is secured.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[repr(u8)]
+pub(in crate::link::rendezvous) enum Opening {
+    Handshake { request_ticket: bool },
+    Resume { request_ticket: bool },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Preamble {
+    magic: [u8; 8],
+    version: u8,
+    opening: Opening,
+}
+
+impl Opening {
+    pub fn to_bytes(self) -> Vec<u8> {
+        bincode::serialize(&Preamble {
+            magic: OPENING_MAGIC,
+            version: OPENING_VERSION,
+            opening: self,
+        })
+        .unwrap() // `Preamble`s are always serializable
+    }
+
+    // Returns `None` if `bytes` were not sent by a compatible `Connector`
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if !bytes.starts_with(&OPENING_MAGIC) {
+            return None;
+        }
+
+        let preamble = bincode::deserialize::<Preamble>(bytes).ok()?;
+
+        if preamble.version == OPENING_VERSION {
+            Some(preamble.opening)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versioned() {
+        let bytes = Opening::Resume {
+            request_ticket: true,
+        }
+        .to_bytes();
+
+        match Opening::from_bytes(&bytes) {
+            Some(Opening::Resume {
+                request_ticket: true,
+            }) => (),
+            opening => panic!("unexpected opening: {:?}", opening),
+        }
+
+        // A pre-`Opening` `Connector` starts with its ephemeral key
+        assert!(Opening::from_bytes(&[0u8; 32]).is_none());
+
+        let mut future = bytes.clone();
+        future[OPENING_MAGIC.len()] = OPENING_VERSION + 1;
+
+        assert!(Opening::from_bytes(&future).is_none());
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
-    crypto::{Identity, KeyCard},
+    crypto::{Identity, KeyCard, PeerRecord, Succession},
     link::rendezvous::ShardId,
 };
 use serde::{Deserialize, Serialize};
 
+// `Request`s are `bincode`-serialized: to preserve the discriminants
+// of existing variants, new variants must be appended at the end
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub(in crate::link::rendezvous) enum Request {
@@ -13,4 +15,10 @@ pub(in crate::link::rendezvous) enum Request {
     GetShard(ShardId),
     GetCard(Identity),
     GetAddress(Identity),
+
+    PublishSuccession(Succession),
+    GetLineage(Identity),
+
+    PublishRecord(PeerRecord),
+    GetRecord(Identity),
 }
@@ -1,7 +1,12 @@
-use crate::{crypto::KeyCard, link::rendezvous::ShardId};
+use crate::{
+    crypto::{KeyCard, Lineage, PeerRecord},
+    link::rendezvous::ShardId,
+};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 
+// `Response`s are `bincode`-serialized: to preserve the discriminants
+// of existing variants, new variants must be appended at the end
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub(in crate::link::rendezvous) enum Response {
@@ -18,4 +23,16 @@ pub(in crate::link::rendezvous) enum Response {
     ShardIncomplete,
     CardUnknown,
     AddressUnknown,
+
+    AcknowledgeSuccession,
+    Lineage(Lineage),
+    SuccessionInvalid,
+
+    AcknowledgeRecord,
+    Record(PeerRecord),
+    RecordExpired,
+    RecordStale,
+    RecordUnknown,
+
+    LineageFull,
 }
@@ -1,7 +1,7 @@
 use crate::{
-    crypto::{Identity, KeyCard},
+    crypto::{Identity, KeyCard, Lineage, PeerRecord},
     link::rendezvous::{Request, Response, ServerSettings, ShardId},
-    net::PlainConnection,
+    net::{ConnectionSettings, PlainConnection},
     sync::fuse::Fuse,
 };
 use doomstack::{here, Doom, ResultExt, Top};
@@ -38,6 +38,9 @@ struct Database {
     cards: HashMap<Identity, KeyCard>,
     membership: HashMap<Identity, Option<ShardId>>,
     addresses: HashMap<Identity, SocketAddr>,
+    lineages: HashMap<Identity, Lineage>,
+    successors: HashMap<Identity, Identity>,
+    records: HashMap<Identity, PeerRecord>,
 }
 
 impl Server {
@@ -54,6 +57,9 @@ impl Server {
             cards: HashMap::new(),
             membership: HashMap::new(),
             addresses: HashMap::new(),
+            lineages: HashMap::new(),
+            successors: HashMap::new(),
+            records: HashMap::new(),
         }));
 
         let fuse = Fuse::new();
@@ -100,6 +106,12 @@ impl Server {
         mut connection: PlainConnection,
         mut address: SocketAddr,
     ) -> Result<(), Top<ServeError>> {
+        // Requests come from unauthenticated clients
+        connection.configure(ConnectionSettings {
+            max_message_size: settings.max_request_size,
+            ..Default::default()
+        });
+
         let request: Request = connection
             .receive()
             .await
@@ -120,6 +132,13 @@ impl Server {
                         Response::AlreadyPublished(membership)
                     }
                 }
+                Request::PublishCard(card, _)
+                    if database.successors.contains_key(&card.identity()) =>
+                {
+                    // `card` was rotated out, and cannot be published again
+                    let current = database.successors[&card.identity()];
+                    Response::AlreadyPublished(database.membership[&current])
+                }
                 Request::PublishCard(_, Some(shard))
                     if (shard as usize) >= database.shards.len() =>
                 {
@@ -142,6 +161,34 @@ impl Server {
                     Response::AcknowledgeCard
                 }
 
+                Request::PublishSuccession(succession)
+                    if !database.cards.contains_key(&succession.previous()) =>
+                {
+                    Response::CardUnknown
+                }
+                Request::PublishSuccession(succession) => {
+                    let previous = succession.previous();
+                    let next = succession.next().identity();
+
+                    let mut lineage = database
+                        .lineages
+                        .get(&previous)
+                        .cloned()
+                        .unwrap_or_else(|| Lineage::new(database.cards[&previous].clone()));
+
+                    if lineage.identities().count() >= settings.max_lineage_length {
+                        Response::LineageFull
+                    } else if database.cards.contains_key(&next)
+                        || database.successors.contains_key(&next)
+                        || lineage.extend(succession).is_err()
+                    {
+                        Response::SuccessionInvalid
+                    } else {
+                        database.rotate(previous, lineage);
+                        Response::AcknowledgeSuccession
+                    }
+                }
+
                 Request::AdvertisePort(identity, port) => {
                     address.set_port(port);
                     database.addresses.insert(identity, address);
@@ -149,6 +196,22 @@ impl Server {
                     Response::AcknowledgePort
                 }
 
+                // `record` was verified upon deserialization
+                Request::PublishRecord(record) if record.is_expired() => Response::RecordExpired,
+                Request::PublishRecord(record)
+                    if database
+                        .records
+                        .get(&record.identity())
+                        .map(|current| !record.supersedes(current))
+                        .unwrap_or(false) =>
+                {
+                    Response::RecordStale
+                }
+                Request::PublishRecord(record) => {
+                    database.store_record(record, settings.max_records);
+                    Response::AcknowledgeRecord
+                }
+
                 Request::GetShard(shard) if (shard as usize) >= database.shards.len() => {
                     Response::ShardIdInvalid
                 }
@@ -168,6 +231,8 @@ impl Server {
                 }
 
                 Request::GetCard(identity) => {
+                    let identity = database.resolve(identity);
+
                     if let Some(card) = database.cards.get(&identity) {
                         Response::Card(card.clone())
                     } else {
@@ -176,12 +241,35 @@ impl Server {
                 }
 
                 Request::GetAddress(identity) => {
+                    let identity = database.resolve(identity);
+
                     if let Some(address) = database.addresses.get(&identity) {
                         Response::Address(address.clone())
                     } else {
                         Response::AddressUnknown
                     }
                 }
+
+                Request::GetLineage(identity) => {
+                    let identity = database.resolve(identity);
+
+                    if let Some(lineage) = database.lineages.get(&identity) {
+                        Response::Lineage(lineage.clone())
+                    } else if let Some(card) = database.cards.get(&identity) {
+                        Response::Lineage(Lineage::new(card.clone()))
+                    } else {
+                        Response::CardUnknown
+                    }
+                }
+
+                Request::GetRecord(identity) => match database.records.get(&identity) {
+                    Some(record) if !record.is_expired() => Response::Record(record.clone()),
+                    Some(_) => {
+                        database.records.remove(&identity);
+                        Response::RecordUnknown
+                    }
+                    None => Response::RecordUnknown,
+                },
             }
         };
 
@@ -193,3 +281,68 @@ impl Server {
         Ok(())
     }
 }
+
+impl Database {
+    // Maps an `identity` that was rotated out to its current successor
+    fn resolve(&self, identity: Identity) -> Identity {
+        self.successors.get(&identity).copied().unwrap_or(identity)
+    }
+
+    // Stores `record`, replacing any previous `PeerRecord` of the same
+    // `Identity`. If `max_records` records are already stored, expired
+    // records are evicted first, then the soonest-expiring record.
+    fn store_record(&mut self, record: PeerRecord, max_records: usize) {
+        if max_records == 0 {
+            return;
+        }
+
+        let identity = record.identity();
+
+        if !self.records.contains_key(&identity) && self.records.len() >= max_records {
+            self.records.retain(|_, record| !record.is_expired());
+
+            if self.records.len() >= max_records {
+                let soonest = self
+                    .records
+                    .iter()
+                    .min_by_key(|(_, record)| record.expiry())
+                    .map(|(identity, _)| *identity)
+                    .unwrap();
+
+                self.records.remove(&soonest);
+            }
+        }
+
+        self.records.insert(identity, record);
+    }
+
+    // Replaces `previous` with the current `KeyCard` in `lineage`, which
+    // inherits the shard membership and address of `previous`
+    fn rotate(&mut self, previous: Identity, lineage: Lineage) {
+        let card = lineage.current().clone();
+        let next = card.identity();
+
+        self.cards.remove(&previous);
+        self.cards.insert(next, card);
+
+        let membership = self.membership.remove(&previous).unwrap();
+
+        if let Some(shard) = membership {
+            self.shards[shard as usize].remove(&previous);
+            self.shards[shard as usize].insert(next);
+        }
+
+        self.membership.insert(next, membership);
+
+        if let Some(address) = self.addresses.get(&previous).cloned() {
+            self.addresses.entry(next).or_insert(address);
+        }
+
+        for historical in lineage.identities().filter(|identity| *identity != next) {
+            self.successors.insert(historical, next);
+        }
+
+        self.lineages.remove(&previous);
+        self.lineages.insert(next, lineage);
+    }
+}
@@ -1,12 +1,20 @@
 #[derive(Debug, Clone)]
 pub struct ServerSettings {
     pub shard_sizes: Vec<usize>,
+    pub max_request_size: usize,
+    pub max_records: usize,
+    /// Maximum number of `KeyCard`s in a `Lineage` (genesis included):
+    /// once reached, further `Succession`s are rejected.
+    pub max_lineage_length: usize,
 }
 
 impl Default for ServerSettings {
     fn default() -> Self {
         ServerSettings {
             shard_sizes: vec![4],
+            max_request_size: 1 << 16, // 64 KiB
+            max_records: 1 << 16,
+            max_lineage_length: 64,
         }
     }
 }
@@ -1,6 +1,9 @@
-use crate::net::{ReceiverSettings, SenderSettings};
+use crate::{
+    crypto::primitives::channel::ChannelSettings,
+    net::{ReceiverSettings, SenderSettings},
+};
 use std::{
-    sync::atomic::{AtomicU64, Ordering},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
     time::Duration,
 };
 
@@ -8,13 +11,30 @@ use std::{
 pub struct ConnectionSettings {
     pub send_timeout: Option<Duration>,
     pub receive_timeout: Option<Duration>,
+    /// Maximum size of a received message. By default, messages are only
+    /// bounded by their 32-bit size prefix (i.e., 4 GiB), as they always
+    /// were. While securing a connection, and until its remote end is
+    /// authenticated, received messages are additionally capped at 16 KiB.
+    pub max_message_size: usize,
+    /// Used to secure the connection. Both ends of a `SecureConnection`
+    /// exchange their `channel_settings` while securing it, then both use
+    /// the lower of the two values of each threshold. Changing
+    /// `channel_settings` has no effect on connections that are already
+    /// secure.
+    pub channel_settings: ChannelSettings,
 }
 
 const SEND_TIMEOUT_DEFAULT: u64 = 0;
 const RECEIVE_TIMEOUT_DEFAULT: u64 = 0;
+const MAX_MESSAGE_SIZE_DEFAULT: usize = u32::MAX as usize; // Largest size a unit can announce
 
 static SEND_TIMEOUT: AtomicU64 = AtomicU64::new(SEND_TIMEOUT_DEFAULT);
 static RECEIVE_TIMEOUT: AtomicU64 = AtomicU64::new(RECEIVE_TIMEOUT_DEFAULT);
+static MAX_MESSAGE_SIZE: AtomicUsize = AtomicUsize::new(MAX_MESSAGE_SIZE_DEFAULT);
+
+// Zero stands for `ChannelSettings::default()`
+static REKEY_MESSAGES: AtomicU64 = AtomicU64::new(0);
+static REKEY_BYTES: AtomicU64 = AtomicU64::new(0);
 
 impl Default for ConnectionSettings {
     fn default() -> Self {
@@ -34,9 +54,23 @@ impl Default for ConnectionSettings {
             Some(Duration::from_micros(receive_timeout))
         };
 
+        let mut channel_settings = ChannelSettings::default();
+
+        match REKEY_MESSAGES.load(Ordering::Relaxed) {
+            0 => {}
+            rekey_messages => channel_settings.rekey_messages = rekey_messages,
+        }
+
+        match REKEY_BYTES.load(Ordering::Relaxed) {
+            0 => {}
+            rekey_bytes => channel_settings.rekey_bytes = rekey_bytes,
+        }
+
         ConnectionSettings {
             send_timeout,
             receive_timeout,
+            max_message_size: MAX_MESSAGE_SIZE.load(Ordering::Relaxed),
+            channel_settings,
         }
     }
 }
@@ -49,6 +83,7 @@ impl ConnectionSettings {
             },
             ReceiverSettings {
                 receive_timeout: self.receive_timeout,
+                max_message_size: self.max_message_size,
             },
         )
     }
@@ -78,7 +113,22 @@ impl ConnectionSettings {
             0
         };
 
+        if settings.max_message_size == 0 {
+            panic!("called `ConnectionSettings::set_default` with a null `max_message_size`")
+        }
+
+        if settings.channel_settings.rekey_messages == 0 {
+            panic!("called `ConnectionSettings::set_default` with a null `rekey_messages`")
+        }
+
+        if settings.channel_settings.rekey_bytes == 0 {
+            panic!("called `ConnectionSettings::set_default` with a null `rekey_bytes`")
+        }
+
         SEND_TIMEOUT.store(send_timeout, Ordering::Relaxed);
         RECEIVE_TIMEOUT.store(receive_timeout, Ordering::Relaxed);
+        MAX_MESSAGE_SIZE.store(settings.max_message_size, Ordering::Relaxed);
+        REKEY_MESSAGES.store(settings.channel_settings.rekey_messages, Ordering::Relaxed);
+        REKEY_BYTES.store(settings.channel_settings.rekey_bytes, Ordering::Relaxed);
     }
 }
@@ -7,6 +7,7 @@ mod plain_connection;
 mod plain_receiver;
 mod plain_sender;
 mod plex;
+mod receive_stream;
 mod receiver_settings;
 mod secure_connection;
 mod secure_receiver;
@@ -17,6 +18,9 @@ mod session_connector;
 mod session_control;
 mod session_listener;
 mod socket;
+mod ticket;
+mod ticket_store;
+mod ticket_store_settings;
 mod unit_receiver;
 mod unit_sender;
 
@@ -27,7 +31,7 @@ pub mod traits;
 pub mod test;
 
 use session_control::SessionControl;
-use unit_receiver::UnitReceiver;
+use unit_receiver::{UnitReceiver, UnitReceiverError};
 use unit_sender::UnitSender;
 
 pub use connection_settings::ConnectionSettings;
@@ -45,6 +49,7 @@ pub use plex::{
     MultiplexId, MultiplexSettings, Plex, PlexConnector, PlexConnectorSettings, PlexListener,
     PlexListenerSettings, PlexSettings,
 };
+pub use receive_stream::ReceiveStream;
 pub use receiver_settings::ReceiverSettings;
 pub use secure_connection::{SecureConnection, SecureConnectionError};
 pub use secure_receiver::SecureReceiver;
@@ -54,3 +59,6 @@ pub use session::Session;
 pub use session_connector::SessionConnector;
 pub use session_listener::SessionListener;
 pub use socket::Socket;
+pub use ticket::{Ticket, TicketKey};
+pub use ticket_store::TicketStore;
+pub use ticket_store_settings::TicketStoreSettings;
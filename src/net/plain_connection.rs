@@ -1,5 +1,9 @@
-use crate::net::{
-    ConnectionSettings, PlainReceiver, PlainSender, SecureConnection, SecureConnectionError, Socket,
+use crate::{
+    crypto::{primitives::channel::ChannelSettings, Identity, KeyCard, KeyChain},
+    net::{
+        ConnectionSettings, PlainReceiver, PlainSender, SecureConnection, SecureConnectionError,
+        Socket, Ticket, TicketKey,
+    },
 };
 use doomstack::{here, Doom, ResultExt, Top};
 use serde::{de::DeserializeOwned, Serialize};
@@ -8,6 +12,7 @@ use tokio::io;
 pub struct PlainConnection {
     sender: PlainSender,
     receiver: PlainReceiver,
+    channel_settings: ChannelSettings,
 }
 
 #[derive(Doom)]
@@ -15,6 +20,15 @@ pub enum PlainConnectionError {
     #[doom(description("Failed to deserialize: {}", source))]
     #[doom(wrap(deserialize_failed))]
     DeserializeFailed { source: bincode::Error },
+    #[doom(description(
+        "Message too large ({} bytes, at most {} allowed)",
+        size,
+        max_message_size
+    ))]
+    MessageTooLarge {
+        size: usize,
+        max_message_size: usize,
+    },
     #[doom(description("Mismatched halves"))]
     MismatchedHalves,
     #[doom(description("Failed to read: {}", source))]
@@ -44,12 +58,17 @@ impl PlainConnection {
         let (read_half, write_half) = io::split(socket);
 
         let settings = ConnectionSettings::default();
+        let channel_settings = settings.channel_settings.clone();
         let (sender_settings, receiver_settings) = settings.split();
 
         let sender = PlainSender::new(write_half, sender_settings);
         let receiver = PlainReceiver::new(read_half, receiver_settings);
 
-        PlainConnection { sender, receiver }
+        PlainConnection {
+            sender,
+            receiver,
+            channel_settings,
+        }
     }
 
     pub fn join(
@@ -57,19 +76,34 @@ impl PlainConnection {
         receiver: PlainReceiver,
     ) -> Result<Self, Top<PlainConnectionError>> {
         if receiver.read_half().is_pair_of(sender.write_half()) {
-            Ok(Self { sender, receiver })
+            Ok(Self {
+                sender,
+                receiver,
+                channel_settings: ConnectionSettings::default().channel_settings,
+            })
         } else {
             PlainConnectionError::MismatchedHalves.fail().spot(here!())
         }
     }
 
     pub fn configure(&mut self, settings: ConnectionSettings) {
+        self.channel_settings = settings.channel_settings.clone();
         let (sender_settings, receiver_settings) = settings.split();
 
         self.sender.configure(sender_settings);
         self.receiver.configure(receiver_settings);
     }
 
+    pub(in crate::net) fn channel_settings(&self) -> &ChannelSettings {
+        &self.channel_settings
+    }
+
+    // Caps the size of received messages at `limit` (e.g., while the remote
+    // is not yet authenticated), returning the previous cap
+    pub(in crate::net) fn restrict_message_size(&mut self, limit: usize) -> usize {
+        self.receiver.restrict_message_size(limit)
+    }
+
     pub fn free_send_buffer(&mut self) {
         self.sender.free_buffer();
     }
@@ -89,6 +123,35 @@ impl PlainConnection {
         self.sender.send_bytes(message).await
     }
 
+    /// Sends all `messages` using as few writes as possible.
+    pub async fn send_many<'m, M, I>(
+        &mut self,
+        messages: I,
+    ) -> Result<(), Top<PlainConnectionError>>
+    where
+        M: 'm + Serialize,
+        I: IntoIterator<Item = &'m M>,
+    {
+        self.sender.send_many(messages).await
+    }
+
+    /// Queues `message` for sending: queued messages are written
+    /// together by the next `flush` (or `send`).
+    pub fn feed<M>(&mut self, message: &M) -> Result<(), Top<PlainConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.sender.feed(message)
+    }
+
+    pub fn feed_bytes(&mut self, message: &[u8]) {
+        self.sender.feed_bytes(message);
+    }
+
+    pub async fn flush(&mut self) -> Result<(), Top<PlainConnectionError>> {
+        self.sender.flush().await
+    }
+
     pub async fn receive<M>(&mut self) -> Result<M, Top<PlainConnectionError>>
     where
         M: DeserializeOwned,
@@ -107,6 +170,57 @@ impl PlainConnection {
     pub async fn secure(self) -> Result<SecureConnection, Top<SecureConnectionError>> {
         SecureConnection::new(self).await
     }
+
+    /// Secures and mutually authenticates this `PlainConnection` in a
+    /// single handshake, returning the remote `KeyCard`. The remote end
+    /// must call `accept_handshake`.
+    ///
+    /// Unlike `secure` followed by `SecureConnection::authenticate`, both
+    /// `Identity`s are bound to the keys of the resulting `SecureConnection`,
+    /// the local `Identity` is never revealed to passive observers, and
+    /// messages can be sent right after the handshake's third flight.
+    pub async fn initiate_handshake(
+        self,
+        keychain: &KeyChain,
+    ) -> Result<(SecureConnection, KeyCard), Top<SecureConnectionError>> {
+        SecureConnection::initiate(self, keychain).await
+    }
+
+    /// Responds to a remote `initiate_handshake`, returning the remote `KeyCard`.
+    pub async fn accept_handshake(
+        self,
+        keychain: &KeyChain,
+    ) -> Result<(SecureConnection, KeyCard), Top<SecureConnectionError>> {
+        SecureConnection::respond(self, keychain).await
+    }
+
+    /// Secures this `PlainConnection` using a resumption `Ticket`, previously
+    /// obtained from `ticket.server()` by `SecureConnection::receive_ticket`.
+    /// The remote end must call `accept_resumption`.
+    ///
+    /// No `KeyCard` or signature is exchanged, but the resulting
+    /// `SecureConnection` is still encrypted under fresh keys.
+    pub async fn resume(
+        self,
+        ticket: &Ticket,
+    ) -> Result<SecureConnection, Top<SecureConnectionError>> {
+        SecureConnection::resume(self, ticket).await
+    }
+
+    /// Responds to a remote `resume`, returning the `Identity` to which
+    /// the remote `Ticket` was issued.
+    ///
+    /// # Errors
+    ///
+    /// If the remote `Ticket` was not issued under `ticket_key`, is expired
+    /// or was already used, or if the remote end fails to prove that it
+    /// holds the `Ticket`, a `ResumeFailed` error variant will be returned.
+    pub async fn accept_resumption(
+        self,
+        ticket_key: &TicketKey,
+    ) -> Result<(SecureConnection, Identity), Top<SecureConnectionError>> {
+        SecureConnection::accept_resumption(self, ticket_key).await
+    }
 }
 
 impl<S> From<S> for PlainConnection
@@ -181,4 +295,52 @@ mod tests {
 
         bob_task.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn message_too_large() {
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            // Handshakes reject oversized messages from unauthenticated remotes
+            assert!(bob_connection.secure().await.is_err());
+        });
+
+        let mut alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        alice_connection.send(&vec![0u8; 1 << 16]).await.unwrap();
+
+        bob_task.await.unwrap();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let mut bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            bob_connection.configure(ConnectionSettings {
+                max_message_size: 1024,
+                ..Default::default()
+            });
+
+            match bob_connection.receive::<Vec<u8>>().await.unwrap_err().top() {
+                PlainConnectionError::MessageTooLarge {
+                    size,
+                    max_message_size,
+                } => {
+                    assert!(*size > 4096);
+                    assert_eq!(*max_message_size, 1024);
+                }
+                error => panic!("unexpected error upon receiving large message: {}", error),
+            }
+        });
+
+        let mut alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        alice_connection.send(&vec![0u8; 4096]).await.unwrap();
+
+        bob_task.await.unwrap();
+    }
 }
@@ -1,10 +1,14 @@
 use crate::{
     crypto::primitives::channel::Receiver as ChannelReceiver,
-    net::{PlainConnectionError, ReceiverSettings, SecureReceiver, Socket, UnitReceiver},
+    net::{
+        PlainConnectionError, ReceiverSettings, SecureReceiver, Socket, UnitReceiver,
+        UnitReceiverError,
+    },
     time,
 };
 use doomstack::{here, Doom, ResultExt, Top};
 use serde::de::DeserializeOwned;
+use std::cmp;
 use tokio::io::ReadHalf;
 
 pub struct PlainReceiver {
@@ -27,6 +31,13 @@ impl PlainReceiver {
         self.settings = settings;
     }
 
+    pub(in crate::net) fn restrict_message_size(&mut self, limit: usize) -> usize {
+        let max_message_size = self.settings.max_message_size;
+        self.settings.max_message_size = cmp::min(max_message_size, limit);
+
+        max_message_size
+    }
+
     pub(in crate::net) fn read_half(&self) -> &ReadHalf<Box<dyn Socket>> {
         self.unit_receiver.read_half()
     }
@@ -54,12 +65,29 @@ impl PlainReceiver {
     }
 
     async fn receive_unit(&mut self) -> Result<(), Top<PlainConnectionError>> {
-        time::optional_timeout(self.settings.receive_timeout, self.unit_receiver.receive())
-            .await
-            .pot(PlainConnectionError::ReceiveTimeout, here!())?
-            .map_err(PlainConnectionError::read_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())
+        let result = time::optional_timeout(
+            self.settings.receive_timeout,
+            self.unit_receiver.receive(self.settings.max_message_size),
+        )
+        .await
+        .pot(PlainConnectionError::ReceiveTimeout, here!())?;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(UnitReceiverError::MessageTooLarge { size }) => {
+                PlainConnectionError::MessageTooLarge {
+                    size,
+                    max_message_size: self.settings.max_message_size,
+                }
+                .fail()
+                .spot(here!())
+            }
+            Err(UnitReceiverError::ReadFailed(error)) => {
+                Err(PlainConnectionError::read_failed(error))
+                    .map_err(Doom::into_top)
+                    .spot(here!())
+            }
+        }
     }
 
     pub(in crate::net) fn secure(self, channel_receiver: ChannelReceiver) -> SecureReceiver {
@@ -36,6 +36,38 @@ impl PlainSender {
     }
 
     pub async fn send<M>(&mut self, message: &M) -> Result<(), Top<PlainConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.feed(message)?;
+        self.flush().await
+    }
+
+    pub async fn send_bytes(&mut self, message: &[u8]) -> Result<(), Top<PlainConnectionError>> {
+        self.feed_bytes(message);
+        self.flush().await
+    }
+
+    /// Sends all `messages` using as few writes as possible.
+    pub async fn send_many<'m, M, I>(
+        &mut self,
+        messages: I,
+    ) -> Result<(), Top<PlainConnectionError>>
+    where
+        M: 'm + Serialize,
+        I: IntoIterator<Item = &'m M>,
+    {
+        for message in messages {
+            self.feed(message)?;
+        }
+
+        self.flush().await
+    }
+
+    /// Queues `message` for sending, without writing it to the underlying
+    /// socket. Queued messages are sent, in order, by the next `flush`
+    /// (or by the next `send`).
+    pub fn feed<M>(&mut self, message: &M) -> Result<(), Top<PlainConnectionError>>
     where
         M: Serialize,
     {
@@ -44,17 +76,22 @@ impl PlainSender {
             .map_err(Doom::into_top)
             .spot(here!())?;
 
-        self.send_unit().await
+        self.unit_sender.feed();
+        Ok(())
     }
 
-    pub async fn send_bytes(&mut self, message: &[u8]) -> Result<(), Top<PlainConnectionError>> {
+    pub fn feed_bytes(&mut self, message: &[u8]) {
         self.unit_sender.as_vec().clear();
         self.unit_sender.as_vec().extend_from_slice(message);
-
-        self.send_unit().await
+        self.unit_sender.feed();
     }
 
-    async fn send_unit(&mut self) -> Result<(), Top<PlainConnectionError>> {
+    /// Writes all queued messages to the underlying socket.
+    ///
+    /// If a `flush` fails or times out, the remote end might have received
+    /// only part of the queued messages: all further `flush`es (and `send`s)
+    /// fail, and the connection should be dropped.
+    pub async fn flush(&mut self) -> Result<(), Top<PlainConnectionError>> {
         time::optional_timeout(self.settings.send_timeout, self.unit_sender.flush())
             .await
             .pot(PlainConnectionError::SendTimeout, here!())?
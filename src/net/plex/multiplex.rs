@@ -9,6 +9,7 @@ use crate::{
     sync::fuse::Fuse,
 };
 use doomstack::{here, Doom, ResultExt, Top};
+use futures::FutureExt;
 use std::{
     collections::HashMap,
     sync::{
@@ -153,7 +154,11 @@ impl Multiplex {
         let fuse = Fuse::new();
 
         fuse.spawn(Multiplex::route_in(receiver, run_route_in_inlet));
-        fuse.spawn(Multiplex::route_out(sender, route_out_outlet));
+        fuse.spawn(Multiplex::route_out(
+            sender,
+            route_out_outlet,
+            settings.route_out_batch_size,
+        ));
 
         let mut plex_handles = HashMap::new();
 
@@ -280,6 +285,7 @@ impl Multiplex {
     async fn route_out(
         mut sender: SecureSender,
         mut route_out_outlet: PayloadOutlet,
+        batch_size: usize,
     ) -> Result<(), Top<RouteOutError>> {
         loop {
             let payload = if let Some(payload) = route_out_outlet.recv().await {
@@ -289,21 +295,38 @@ impl Multiplex {
                 return Ok(());
             };
 
+            Multiplex::feed(&mut sender, payload)?;
+
+            // Feed any other `Payload` that is already queued, so that all of
+            // them are written together
+            for _ in 1..batch_size {
+                match route_out_outlet.recv().now_or_never() {
+                    Some(Some(payload)) => Multiplex::feed(&mut sender, payload)?,
+                    _ => break,
+                }
+            }
+
             sender
-                .send(&payload.header())
+                .flush()
                 .await
                 .pot(RouteOutError::ConnectionError, here!())?;
+        }
+    }
 
-            match payload {
-                Payload::Message { message, .. } => match message.security {
-                    Security::Secure => sender.send_bytes(message.message.as_slice()).await,
-                    Security::Plain => sender.send_plain_bytes(message.message.as_slice()).await,
-                    Security::Raw => sender.send_raw_bytes(message.message.as_slice()).await,
-                }
-                .pot(RouteOutError::ConnectionError, here!())?,
-                _ => (),
+    fn feed(sender: &mut SecureSender, payload: Payload) -> Result<(), Top<RouteOutError>> {
+        sender
+            .feed(&payload.header())
+            .pot(RouteOutError::ConnectionError, here!())?;
+
+        if let Payload::Message { message, .. } = payload {
+            match message.security {
+                Security::Secure => sender.feed_bytes(message.message.as_slice()),
+                Security::Plain => sender.feed_plain_bytes(message.message.as_slice()),
+                Security::Raw => sender.feed_raw_bytes(message.message.as_slice()),
             }
         }
+
+        Ok(())
     }
 }
 
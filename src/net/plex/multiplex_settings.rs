@@ -5,6 +5,7 @@ pub struct MultiplexSettings {
     pub run_plex_channel_capacity: usize,
     pub run_route_in_channel_capacity: usize,
     pub route_out_channel_capacity: usize,
+    pub route_out_batch_size: usize,
     pub accept_channel_capacity: usize,
     pub plex_settings: PlexSettings,
 }
@@ -15,6 +16,7 @@ impl Default for MultiplexSettings {
             run_plex_channel_capacity: 128,
             run_route_in_channel_capacity: 128,
             route_out_channel_capacity: 128,
+            route_out_batch_size: 32,
             accept_channel_capacity: 128,
             plex_settings: Default::default(),
         }
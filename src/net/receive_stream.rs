@@ -0,0 +1,150 @@
+use crate::net::{SecureConnectionError, SecureReceiver};
+use doomstack::Top;
+use futures::{future::BoxFuture, ready};
+use std::{
+    cmp,
+    convert::TryInto,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{self, AsyncRead, ErrorKind, ReadBuf};
+
+// A stream is a sequence of frames, each encrypted as a regular message.
+// The first byte of every frame is a tag: `DATA_TAG` frames carry a chunk
+// of the stream, the last frame of a stream is either an `END_TAG` frame
+// (followed by the little-endian `u64` length of the stream) or, if the
+// sender failed to read its source, an `ABORT_TAG` frame.
+pub(in crate::net) const DATA_TAG: u8 = 0;
+pub(in crate::net) const END_TAG: u8 = 1;
+pub(in crate::net) const ABORT_TAG: u8 = 2;
+
+pub(in crate::net) const STREAM_CHUNK_SIZE: usize = 1 << 16; // 64 KiB
+
+type Frame<'a> = (
+    &'a mut SecureReceiver,
+    Result<Vec<u8>, Top<SecureConnectionError>>,
+);
+
+/// A stream received on a `SecureConnection`, obtained from
+/// `SecureConnection::receive_stream`.
+///
+/// Frames are only received as the `ReceiveStream` is read, so a slow
+/// reader exerts backpressure on the sender. Reading fails if the stream
+/// is truncated or aborted by the sender. A `ReceiveStream` should be read
+/// to its end: otherwise, its remaining frames are left on the connection.
+pub struct ReceiveStream<'a> {
+    receiver: Option<&'a mut SecureReceiver>,
+    pending: Option<BoxFuture<'a, Frame<'a>>>,
+    chunk: Vec<u8>,
+    offset: usize,
+    length: u64,
+    done: bool,
+}
+
+impl<'a> ReceiveStream<'a> {
+    pub(in crate::net) fn new(receiver: &'a mut SecureReceiver) -> Self {
+        ReceiveStream {
+            receiver: Some(receiver),
+            pending: None,
+            chunk: Vec::new(),
+            offset: 0,
+            length: 0,
+            done: false,
+        }
+    }
+
+    fn process(&mut self, frame: Vec<u8>) -> io::Result<()> {
+        match frame.first() {
+            Some(&DATA_TAG) => {
+                self.length += (frame.len() - 1) as u64;
+                self.chunk = frame;
+                self.offset = 1;
+
+                Ok(())
+            }
+            Some(&END_TAG) => {
+                let length = frame[1..].try_into().map(u64::from_le_bytes).map_err(|_| {
+                    io::Error::new(ErrorKind::InvalidData, "malformed end of stream")
+                })?;
+
+                if length != self.length {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "stream length does not match end of stream",
+                    ));
+                }
+
+                self.done = true;
+                Ok(())
+            }
+            Some(&ABORT_TAG) => Err(io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "stream aborted by sender",
+            )),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "malformed stream frame",
+            )),
+        }
+    }
+}
+
+impl AsyncRead for ReceiveStream<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.offset < this.chunk.len() {
+                let available = &this.chunk[this.offset..];
+                let size = cmp::min(available.len(), buf.remaining());
+
+                buf.put_slice(&available[..size]);
+                this.offset += size;
+
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.done {
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending.is_none() {
+                let receiver = match this.receiver.take() {
+                    Some(receiver) => receiver,
+                    None => {
+                        // A previous frame failed: the stream is unusable
+                        return Poll::Ready(Err(io::Error::new(
+                            ErrorKind::BrokenPipe,
+                            "stream already failed",
+                        )));
+                    }
+                };
+
+                this.pending = Some(Box::pin(async move {
+                    let frame = receiver.receive_bytes().await;
+                    (receiver, frame)
+                }));
+            }
+
+            let (receiver, frame) = ready!(this.pending.as_mut().unwrap().as_mut().poll(cx));
+            this.pending = None;
+
+            let frame = frame.map_err(|error| {
+                let kind = match error.top() {
+                    SecureConnectionError::ReadFailed { source } => source.kind(),
+                    _ => ErrorKind::InvalidData,
+                };
+
+                io::Error::new(kind, error.top().to_string())
+            })?;
+
+            this.process(frame)?;
+            this.receiver = Some(receiver);
+        }
+    }
+}
@@ -4,12 +4,16 @@ use std::time::Duration;
 #[derive(Debug, Clone)]
 pub struct ReceiverSettings {
     pub receive_timeout: Option<Duration>,
+    pub max_message_size: usize,
 }
 
 impl Default for ReceiverSettings {
     fn default() -> Self {
+        let settings = ConnectionSettings::default();
+
         ReceiverSettings {
-            receive_timeout: ConnectionSettings::default().receive_timeout,
+            receive_timeout: settings.receive_timeout,
+            max_message_size: settings.max_message_size,
         }
     }
 }
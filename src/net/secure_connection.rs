@@ -2,21 +2,47 @@ use crate::{
     crypto::{
         primitives::{
             channel,
-            exchange::{KeyPair, PublicKey},
+            channel::ChannelSettings,
+            exchange::{KeyPair, PublicKey, Role, SharedKey},
+            hash::{Hash, Hasher},
             sign::Signature,
         },
-        KeyCard, KeyChain, Scope, Statement, TalkHeader,
+        Identity, KeyCard, KeyChain, Scope, Statement, TalkHeader,
+    },
+    net::{
+        ticket::{TicketContents, PSK_LENGTH},
+        ConnectionSettings, PlainConnection, ReceiveStream, SecureReceiver, SecureSender, Ticket,
+        TicketKey,
     },
-    net::{ConnectionSettings, PlainConnection, SecureReceiver, SecureSender},
 };
 use doomstack::{here, Doom, ResultExt, Top};
-use serde::{de::DeserializeOwned, Serialize};
-use std::io;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    io, mem,
+    time::{Duration, SystemTime},
+};
+use tokio::io::AsyncRead;
+use zeroize::Zeroize;
+
+// Handshake messages are encrypted under a key derived from the ephemeral
+// exchange, and session messages under a key derived from the full handshake
+// transcript, so that session keys are bound to both `Identity`s
+const HANDSHAKE_PROTOCOL: &[u8] = b"talk 2022-06-01 SecureConnection handshake";
+const HANDSHAKE_KEY_CONTEXT: &str = "talk 2022-06-01 SecureConnection handshake key";
+const SESSION_KEY_CONTEXT: &str = "talk 2022-06-01 SecureConnection session key";
+
+// Until the remote is authenticated, received messages are capped at
+// `HANDSHAKE_MAX_MESSAGE_SIZE`: no handshake message comes close to it
+const HANDSHAKE_MAX_MESSAGE_SIZE: usize = 1 << 14; // 16 KiB
+
+const RESUMPTION_PROTOCOL: &[u8] = b"talk 2022-06-01 SecureConnection resumption";
+const RESUMPTION_KEY_CONTEXT: &str = "talk 2022-06-01 SecureConnection resumption key";
 
 pub struct SecureConnection {
     sender: SecureSender,
     receiver: SecureReceiver,
     keys: Keys,
+    remote_identity: Option<Identity>,
 }
 
 struct Keys {
@@ -35,15 +61,30 @@ pub enum SecureConnectionError {
     DeserializeFailed { source: bincode::Error },
     #[doom(description("Failed to encrypt message"))]
     EncryptFailed,
+    #[doom(description("Failed to complete handshake"))]
+    HandshakeFailed,
+    #[doom(description("Remote `ChannelSettings` are invalid"))]
+    InvalidChannelSettings,
     #[doom(description("Failed to compute message authentication code"))]
     MacComputeFailed,
     #[doom(description("Failed to verify message authentication code"))]
     MacVerifyFailed,
+    #[doom(description(
+        "Message too large ({} bytes, at most {} allowed)",
+        size,
+        max_message_size
+    ))]
+    MessageTooLarge {
+        size: usize,
+        max_message_size: usize,
+    },
     #[doom(description("Failed to read: {}", source))]
     #[doom(wrap(read_failed))]
     ReadFailed { source: io::Error },
     #[doom(description("Timed out while `receive`ing"))]
     ReceiveTimeout,
+    #[doom(description("Failed to resume session"))]
+    ResumeFailed,
     #[doom(description("Failed to `secure`"))]
     SecureFailed,
     #[doom(description("Timed out while `send`ing"))]
@@ -51,6 +92,11 @@ pub enum SecureConnectionError {
     #[doom(description("Failed to serialize: {}", source))]
     #[doom(wrap(serialize_failed))]
     SerializeFailed { source: bincode::Error },
+    #[doom(description("Failed to read stream: {}", source))]
+    #[doom(wrap(stream_failed))]
+    StreamFailed { source: io::Error },
+    #[doom(description("Failed to exchange resumption ticket"))]
+    TicketFailed,
     #[doom(description("Failed to write: {}", source))]
     #[doom(wrap(write_failed))]
     WriteFailed { source: io::Error },
@@ -59,43 +105,450 @@ pub enum SecureConnectionError {
 #[derive(Serialize)]
 struct IdentityChallenge(PublicKey);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum HandshakeRole {
+    Initiator,
+    Responder,
+}
+
+// Signed by each party over the transcript hash at the point where its
+// `Identity` is revealed: `role` prevents a signature from being reflected
+#[derive(Serialize)]
+struct HandshakeTranscript {
+    role: HandshakeRole,
+    transcript: Hash,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HandshakeProof {
+    keycard: KeyCard,
+    signature: Signature,
+}
+
+// Each party sends its `ChannelSettings` along with its ephemeral key:
+// both parties then use the most conservative of the two (see `agree`)
+#[derive(Serialize, Deserialize)]
+struct Ephemeral {
+    key: PublicKey,
+    channel_settings: ChannelSettings,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ResumptionHello {
+    ephemeral: Ephemeral,
+    ticket: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TicketGrant {
+    ticket: Vec<u8>,
+    psk: [u8; PSK_LENGTH],
+    expiry: SystemTime,
+}
+
 impl SecureConnection {
     pub(in crate::net) async fn new(
         mut connection: PlainConnection,
     ) -> Result<Self, Top<SecureConnectionError>> {
+        let max_message_size = connection.restrict_message_size(HANDSHAKE_MAX_MESSAGE_SIZE);
+
         // Run Diffie-Helman
 
         let keypair = KeyPair::random();
-        let local_key = keypair.public();
+        let local = Ephemeral::new(keypair.public(), &connection);
 
         connection
-            .send(&local_key)
+            .send(&local)
             .await
             .pot(SecureConnectionError::SecureFailed, here!())?;
 
-        let remote_key: PublicKey = connection
+        let remote: Ephemeral = connection
             .receive()
             .await
             .pot(SecureConnectionError::SecureFailed, here!())?;
 
-        let (shared_key, role) = keypair.exchange(remote_key);
+        let channel_settings = agree(&local, &remote)?;
+
+        let (shared_key, role) = keypair.exchange(remote.key);
+
+        let mut connection = Self::assemble(
+            connection,
+            shared_key,
+            role,
+            channel_settings,
+            Keys {
+                local: local.key,
+                remote: remote.key,
+            },
+            None,
+        );
+
+        connection.receiver.set_max_message_size(max_message_size);
+
+        Ok(connection)
+    }
+
+    // The handshake follows the pattern of Noise XX, with signatures
+    // standing in for static Diffie-Hellman:
+    //
+    //   -> e
+    //   <- e, ee, { keycard, signature }
+    //   -> { keycard, signature }
+    //
+    // where `{ .. }` is encrypted under the handshake key, and `e` carries
+    // the sender's `ChannelSettings` along with its ephemeral key. The
+    // responder reveals its `Identity` first: the initiator's `Identity` is
+    // only sent to an authenticated responder, and is never visible on the
+    // wire.
+
+    pub(in crate::net) async fn initiate(
+        mut connection: PlainConnection,
+        keychain: &KeyChain,
+    ) -> Result<(Self, KeyCard), Top<SecureConnectionError>> {
+        let max_message_size = connection.restrict_message_size(HANDSHAKE_MAX_MESSAGE_SIZE);
+
+        // -> e
+
+        let keypair = KeyPair::random();
+        let local = Ephemeral::new(keypair.public(), &connection);
+
+        connection
+            .send(&local)
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        // <- e, ee, { keycard, signature }
+
+        let remote: Ephemeral = connection
+            .receive()
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let channel_settings = agree(&local, &remote)?;
+
+        let (shared_key, role) = keypair.exchange(remote.key);
+
+        let (mut handshake_sender, mut handshake_receiver) = channel::channel(
+            shared_key.derive(
+                HANDSHAKE_KEY_CONTEXT,
+                &transcript(&local, &remote, &[]).to_bytes(),
+            ),
+            role,
+        );
+
+        let proof = connection
+            .receive_bytes()
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let proof: HandshakeProof = handshake_receiver
+            .decrypt(&proof)
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let remote_identity = proof.keycard.identity();
+
+        proof
+            .signature
+            .verify(
+                &proof.keycard,
+                &HandshakeTranscript {
+                    role: HandshakeRole::Responder,
+                    transcript: transcript(&local, &remote, &[remote_identity]),
+                },
+            )
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        // -> { keycard, signature }
+
+        let session = transcript(&local, &remote, &[remote_identity, keychain.identity()]);
+
+        let signature = keychain
+            .sign(&HandshakeTranscript {
+                role: HandshakeRole::Initiator,
+                transcript: session,
+            })
+            .unwrap();
+
+        let local_proof = handshake_sender
+            .encrypt(&HandshakeProof {
+                keycard: keychain.keycard(),
+                signature,
+            })
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        connection
+            .send_bytes(&local_proof)
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let mut connection = Self::assemble(
+            connection,
+            shared_key.derive(SESSION_KEY_CONTEXT, &session.to_bytes()),
+            role,
+            channel_settings,
+            Keys {
+                local: local.key,
+                remote: remote.key,
+            },
+            Some(remote_identity),
+        );
+
+        connection.receiver.set_max_message_size(max_message_size);
+
+        Ok((connection, proof.keycard))
+    }
+
+    pub(in crate::net) async fn respond(
+        mut connection: PlainConnection,
+        keychain: &KeyChain,
+    ) -> Result<(Self, KeyCard), Top<SecureConnectionError>> {
+        let max_message_size = connection.restrict_message_size(HANDSHAKE_MAX_MESSAGE_SIZE);
+
+        // -> e
+
+        let remote: Ephemeral = connection
+            .receive()
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        // <- e, ee, { keycard, signature }
+
+        let keypair = KeyPair::random();
+        let local = Ephemeral::new(keypair.public(), &connection);
+
+        let channel_settings = agree(&local, &remote)?;
+
+        let (shared_key, role) = keypair.exchange(remote.key);
+
+        let (mut handshake_sender, mut handshake_receiver) = channel::channel(
+            shared_key.derive(
+                HANDSHAKE_KEY_CONTEXT,
+                &transcript(&remote, &local, &[]).to_bytes(),
+            ),
+            role,
+        );
+
+        let signature = keychain
+            .sign(&HandshakeTranscript {
+                role: HandshakeRole::Responder,
+                transcript: transcript(&remote, &local, &[keychain.identity()]),
+            })
+            .unwrap();
+
+        let local_proof = handshake_sender
+            .encrypt(&HandshakeProof {
+                keycard: keychain.keycard(),
+                signature,
+            })
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        connection
+            .send(&local)
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        connection
+            .send_bytes(&local_proof)
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        // -> { keycard, signature }
+
+        let proof = connection
+            .receive_bytes()
+            .await
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let proof: HandshakeProof = handshake_receiver
+            .decrypt(&proof)
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let session = transcript(
+            &remote,
+            &local,
+            &[keychain.identity(), proof.keycard.identity()],
+        );
+
+        proof
+            .signature
+            .verify(
+                &proof.keycard,
+                &HandshakeTranscript {
+                    role: HandshakeRole::Initiator,
+                    transcript: session,
+                },
+            )
+            .pot(SecureConnectionError::HandshakeFailed, here!())?;
+
+        let mut connection = Self::assemble(
+            connection,
+            shared_key.derive(SESSION_KEY_CONTEXT, &session.to_bytes()),
+            role,
+            channel_settings,
+            Keys {
+                local: local.key,
+                remote: remote.key,
+            },
+            Some(proof.keycard.identity()),
+        );
+
+        connection.receiver.set_max_message_size(max_message_size);
+
+        Ok((connection, proof.keycard))
+    }
+
+    // Resumption replaces the handshake's `KeyCard`s and signatures with
+    // the pre-shared key of a `Ticket`:
+    //
+    //   -> e, ticket
+    //   <- e, ee, psk, { confirmation }
+    //   -> { confirmation }
+    //
+    // A fresh ephemeral exchange is still run, so that every resumed session
+    // is encrypted under fresh keys. The responder's confirmation lets the
+    // initiator detect rejected tickets right away. As `e, ticket` travels in
+    // the clear, the initiator's confirmation proves to the responder that
+    // the initiator holds the `Ticket`'s pre-shared key (the `TicketKey`
+    // additionally rejects replayed tickets).
+
+    pub(in crate::net) async fn resume(
+        mut connection: PlainConnection,
+        ticket: &Ticket,
+    ) -> Result<Self, Top<SecureConnectionError>> {
+        let max_message_size = connection.restrict_message_size(HANDSHAKE_MAX_MESSAGE_SIZE);
+
+        let keypair = KeyPair::random();
+
+        let hello = ResumptionHello {
+            ephemeral: Ephemeral::new(keypair.public(), &connection),
+            ticket: ticket.ticket().to_vec(),
+        };
+
+        connection
+            .send(&hello)
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        let local = hello.ephemeral;
+
+        let remote: Ephemeral = connection
+            .receive()
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        let channel_settings = agree(&local, &remote)?;
+
+        let (shared_key, role) = keypair.exchange(remote.key);
+
+        let mut connection = Self::assemble(
+            connection,
+            resumption_key(&shared_key, ticket.psk(), &local, &remote, ticket.ticket()),
+            role,
+            channel_settings,
+            Keys {
+                local: local.key,
+                remote: remote.key,
+            },
+            Some(ticket.server()),
+        );
+
+        let confirmation: HandshakeRole = connection
+            .receive()
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        if confirmation != HandshakeRole::Responder {
+            return SecureConnectionError::ResumeFailed.fail().spot(here!());
+        }
+
+        connection
+            .send(&HandshakeRole::Initiator)
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        connection.receiver.set_max_message_size(max_message_size);
+
+        Ok(connection)
+    }
+
+    pub(in crate::net) async fn accept_resumption(
+        mut connection: PlainConnection,
+        ticket_key: &TicketKey,
+    ) -> Result<(Self, Identity), Top<SecureConnectionError>> {
+        let max_message_size = connection.restrict_message_size(HANDSHAKE_MAX_MESSAGE_SIZE);
+
+        let hello: ResumptionHello = connection
+            .receive()
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        let contents = ticket_key
+            .open(&hello.ticket)
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        let keypair = KeyPair::random();
+        let local = Ephemeral::new(keypair.public(), &connection);
+        let remote = &hello.ephemeral;
+
+        let channel_settings = agree(&local, remote)?;
+
+        let (shared_key, role) = keypair.exchange(remote.key);
 
-        // Create channel
+        connection
+            .send(&local)
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        let mut connection = Self::assemble(
+            connection,
+            resumption_key(&shared_key, &contents.psk, remote, &local, &hello.ticket),
+            role,
+            channel_settings,
+            Keys {
+                local: local.key,
+                remote: remote.key,
+            },
+            Some(contents.client),
+        );
+
+        connection
+            .send(&HandshakeRole::Responder)
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        let confirmation: HandshakeRole = connection
+            .receive()
+            .await
+            .pot(SecureConnectionError::ResumeFailed, here!())?;
+
+        if confirmation != HandshakeRole::Initiator {
+            return SecureConnectionError::ResumeFailed.fail().spot(here!());
+        }
+
+        connection.receiver.set_max_message_size(max_message_size);
 
-        let (channel_sender, channel_receiver) = channel::channel(shared_key, role);
+        Ok((connection, contents.client))
+    }
 
-        // Create Secure Sender and Receiver
+    fn assemble(
+        connection: PlainConnection,
+        shared_key: SharedKey,
+        role: Role,
+        channel_settings: ChannelSettings,
+        keys: Keys,
+        remote_identity: Option<Identity>,
+    ) -> Self {
+        let (channel_sender, channel_receiver) =
+            channel::channel_with_settings(shared_key, role, channel_settings);
 
         let (plain_sender, plain_receiver) = connection.split();
 
-        Ok(Self {
+        Self {
             sender: plain_sender.secure(channel_sender),
             receiver: plain_receiver.secure(channel_receiver),
-            keys: Keys {
-                local: local_key,
-                remote: remote_key,
-            },
-        })
+            keys,
+            remote_identity,
+        }
     }
 
     pub fn local_key_exchange_key(&self) -> &PublicKey {
@@ -106,6 +559,14 @@ impl SecureConnection {
         &self.keys.remote
     }
 
+    /// Returns the `Identity` of the remote end, if it was authenticated
+    /// (by a handshake, a resumption or `authenticate`).
+    pub fn remote_identity(&self) -> Option<Identity> {
+        self.remote_identity
+    }
+
+    /// Applies `settings`, except for `channel_settings`, which are fixed
+    /// when this `SecureConnection` is established.
     pub fn configure(&mut self, settings: ConnectionSettings) {
         let (sender_settings, receiver_settings) = settings.split();
 
@@ -124,6 +585,24 @@ impl SecureConnection {
     pub async fn authenticate(
         &mut self,
         keychain: &KeyChain,
+    ) -> Result<KeyCard, Top<SecureConnectionError>> {
+        let max_message_size = self
+            .receiver
+            .restrict_message_size(HANDSHAKE_MAX_MESSAGE_SIZE);
+
+        // The message size limit is restored whether or not authentication succeeds
+        let keycard = self.exchange_keycards(keychain).await;
+        self.receiver.set_max_message_size(max_message_size);
+
+        let keycard = keycard?;
+        self.remote_identity = Some(keycard.identity());
+
+        Ok(keycard)
+    }
+
+    async fn exchange_keycards(
+        &mut self,
+        keychain: &KeyChain,
     ) -> Result<KeyCard, Top<SecureConnectionError>> {
         let challenge = IdentityChallenge(self.keys.remote);
         let proof = keychain.sign(&challenge).unwrap();
@@ -155,6 +634,59 @@ impl SecureConnection {
         Ok(keycard)
     }
 
+    /// Issues a resumption `Ticket`, valid for `lifetime`, to the remote
+    /// end of this `SecureConnection`, which must call `receive_ticket`.
+    /// Connections resumed with the `Ticket` will be attributed to the
+    /// remote `Identity` authenticated on this `SecureConnection`.
+    ///
+    /// # Errors
+    ///
+    /// If the remote end was not authenticated, a `TicketFailed` error
+    /// variant will be returned.
+    pub async fn issue_ticket(
+        &mut self,
+        ticket_key: &TicketKey,
+        lifetime: Duration,
+    ) -> Result<(), Top<SecureConnectionError>> {
+        let client = self
+            .remote_identity
+            .ok_or(SecureConnectionError::TicketFailed.into_top())
+            .spot(here!())?;
+
+        let contents = TicketContents::random(client, SystemTime::now() + lifetime);
+
+        let grant = TicketGrant {
+            ticket: ticket_key.seal(&contents),
+            psk: contents.psk,
+            expiry: contents.expiry,
+        };
+
+        self.send(&grant)
+            .await
+            .pot(SecureConnectionError::TicketFailed, here!())
+    }
+
+    /// Receives a resumption `Ticket` from the remote end of this
+    /// `SecureConnection`, which must have been authenticated.
+    pub async fn receive_ticket(&mut self) -> Result<Ticket, Top<SecureConnectionError>> {
+        let server = self
+            .remote_identity
+            .ok_or(SecureConnectionError::TicketFailed.into_top())
+            .spot(here!())?;
+
+        let mut grant: TicketGrant = self
+            .receive()
+            .await
+            .pot(SecureConnectionError::TicketFailed, here!())?;
+
+        Ok(Ticket::new(
+            server,
+            mem::take(&mut grant.ticket),
+            grant.psk,
+            grant.expiry,
+        ))
+    }
+
     pub async fn send<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
     where
         M: Serialize,
@@ -194,6 +726,66 @@ impl SecureConnection {
         self.sender.send_raw_bytes(message).await
     }
 
+    /// Sends all `messages` using as few writes as possible.
+    pub async fn send_many<'m, M, I>(
+        &mut self,
+        messages: I,
+    ) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: 'm + Serialize,
+        I: IntoIterator<Item = &'m M>,
+    {
+        self.sender.send_many(messages).await
+    }
+
+    /// Queues `message` for sending: queued messages are written
+    /// together by the next `flush` (or `send`).
+    pub fn feed<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.sender.feed(message)
+    }
+
+    pub fn feed_bytes(&mut self, message: &[u8]) {
+        self.sender.feed_bytes(message);
+    }
+
+    pub fn feed_plain<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.sender.feed_plain(message)
+    }
+
+    pub fn feed_plain_bytes(&mut self, message: &[u8]) {
+        self.sender.feed_plain_bytes(message);
+    }
+
+    pub fn feed_raw<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.sender.feed_raw(message)
+    }
+
+    pub fn feed_raw_bytes(&mut self, message: &[u8]) {
+        self.sender.feed_raw_bytes(message);
+    }
+
+    pub async fn flush(&mut self) -> Result<(), Top<SecureConnectionError>> {
+        self.sender.flush().await
+    }
+
+    /// Sends the contents of `stream` in chunks, returning the number of
+    /// bytes sent. The remote end must call `receive_stream`.
+    pub async fn send_stream<R>(&mut self, stream: R) -> Result<u64, Top<SecureConnectionError>>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.sender.send_stream(stream).await
+    }
+
     pub async fn receive<M>(&mut self) -> Result<M, Top<SecureConnectionError>>
     where
         M: DeserializeOwned,
@@ -227,6 +819,11 @@ impl SecureConnection {
         self.receiver.receive_raw_bytes().await
     }
 
+    /// Receives a stream sent by a remote `send_stream`.
+    pub fn receive_stream(&mut self) -> ReceiveStream<'_> {
+        self.receiver.receive_stream()
+    }
+
     pub fn split(self) -> (SecureSender, SecureReceiver) {
         (self.sender, self.receiver)
     }
@@ -238,11 +835,99 @@ impl Statement for IdentityChallenge {
     const HEADER: TalkHeader = TalkHeader::SecureConnectionIdentityChallenge;
 }
 
+impl Statement for HandshakeTranscript {
+    const SCOPE: Scope = Scope::talk();
+    type Header = TalkHeader;
+    const HEADER: TalkHeader = TalkHeader::SecureConnectionHandshake;
+}
+
+impl Drop for TicketGrant {
+    fn drop(&mut self) {
+        self.psk.zeroize();
+    }
+}
+
+// Session keys are bound to the fresh ephemeral exchange, to the
+// pre-shared key of the ticket, and to the ticket itself
+impl Ephemeral {
+    fn new(key: PublicKey, connection: &PlainConnection) -> Self {
+        Ephemeral {
+            key,
+            channel_settings: connection.channel_settings().clone(),
+        }
+    }
+
+    fn update(&self, hasher: &mut Hasher) {
+        hasher.update_raw(&self.key.to_bytes());
+        hasher.update_raw(&self.channel_settings.rekey_messages.to_le_bytes());
+        hasher.update_raw(&self.channel_settings.rekey_bytes.to_le_bytes());
+    }
+}
+
+// Returns the `ChannelSettings` both parties use: as both parties compute
+// them from the same `Ephemeral`s, they always agree. `Ephemeral`s are bound
+// to the session keys (see `transcript` and `resumption_key`), so that
+// neither party's `ChannelSettings` can be altered in transit.
+fn agree(
+    local: &Ephemeral,
+    remote: &Ephemeral,
+) -> Result<ChannelSettings, Top<SecureConnectionError>> {
+    let local = &local.channel_settings;
+    let remote = &remote.channel_settings;
+
+    if remote.rekey_messages == 0 || remote.rekey_bytes == 0 {
+        return SecureConnectionError::InvalidChannelSettings
+            .fail()
+            .spot(here!());
+    }
+
+    Ok(ChannelSettings {
+        rekey_messages: local.rekey_messages.min(remote.rekey_messages),
+        rekey_bytes: local.rekey_bytes.min(remote.rekey_bytes),
+    })
+}
+
+fn resumption_key(
+    shared_key: &SharedKey,
+    psk: &[u8; PSK_LENGTH],
+    initiator: &Ephemeral,
+    responder: &Ephemeral,
+    ticket: &[u8],
+) -> SharedKey {
+    let mut hasher = Hasher::new();
+
+    hasher.update_raw(RESUMPTION_PROTOCOL);
+    initiator.update(&mut hasher);
+    responder.update(&mut hasher);
+    hasher.update_raw(ticket);
+
+    shared_key
+        .derive(RESUMPTION_KEY_CONTEXT, psk)
+        .derive(SESSION_KEY_CONTEXT, &hasher.finalize().to_bytes())
+}
+
+fn transcript(initiator: &Ephemeral, responder: &Ephemeral, identities: &[Identity]) -> Hash {
+    let mut hasher = Hasher::new();
+
+    hasher.update_raw(HANDSHAKE_PROTOCOL);
+    initiator.update(&mut hasher);
+    responder.update(&mut hasher);
+
+    for identity in identities {
+        hasher.update_raw(&identity.to_bytes());
+    }
+
+    hasher.finalize()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::net::SocketAddr;
-    use tokio::net::{TcpListener, TcpStream};
+    use tokio::{
+        io::AsyncReadExt,
+        net::{TcpListener, TcpStream},
+    };
 
     async fn new_listener() -> (TcpListener, SocketAddr) {
         let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
@@ -305,9 +990,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn single_send() {
-        const MESSAGE: &str = "Hello Bob, this is Alice!";
-
+    async fn authenticate_failed() {
         let alice_keychain = KeyChain::random();
         let bob_keychain = KeyChain::random();
 
@@ -318,10 +1001,11 @@ mod tests {
 
             let mut bob_connection = bob_connection.secure().await.unwrap();
 
-            bob_connection.authenticate(&bob_keychain).await.unwrap();
-            let message: String = bob_connection.receive().await.unwrap();
+            assert!(bob_connection.authenticate(&bob_keychain).await.is_err());
 
-            assert_eq!(message, MESSAGE);
+            // The message size limit is restored after a failed authentication
+            let message: Vec<u8> = bob_connection.receive().await.unwrap();
+            assert_eq!(message, vec![0u8; 2 * HANDSHAKE_MAX_MESSAGE_SIZE]);
         });
 
         let alice_connection: PlainConnection =
@@ -329,49 +1013,502 @@ mod tests {
 
         let mut alice_connection = alice_connection.secure().await.unwrap();
 
+        // Alice signs the wrong challenge
+        let challenge = IdentityChallenge(alice_connection.keys.local);
+        let proof = alice_keychain.sign(&challenge).unwrap();
+
         alice_connection
-            .authenticate(&alice_keychain)
+            .send(&alice_keychain.keycard())
             .await
             .unwrap();
 
-        alice_connection.send(&String::from(MESSAGE)).await.unwrap();
+        alice_connection.send(&proof).await.unwrap();
+
+        alice_connection
+            .send(&vec![0u8; 2 * HANDSHAKE_MAX_MESSAGE_SIZE])
+            .await
+            .unwrap();
 
         bob_task.await.unwrap();
     }
 
     #[tokio::test]
-    async fn single_send_plain() {
+    async fn handshake() {
         const MESSAGE: &str = "Hello Bob, this is Alice!";
 
         let alice_keychain = KeyChain::random();
         let bob_keychain = KeyChain::random();
 
+        let alice_keycard = alice_keychain.keycard();
+        let bob_keycard = bob_keychain.keycard();
+
         let (bob_listener, bob_address) = new_listener().await;
 
         let bob_task = tokio::spawn(async move {
             let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
 
-            let mut bob_connection = bob_connection.secure().await.unwrap();
+            let (mut bob_connection, remote_keycard) = bob_connection
+                .accept_handshake(&bob_keychain)
+                .await
+                .unwrap();
 
-            bob_connection.authenticate(&bob_keychain).await.unwrap();
-            let message: String = bob_connection.receive_plain().await.unwrap();
+            assert_eq!(remote_keycard, alice_keycard);
 
+            let message: String = bob_connection.receive().await.unwrap();
             assert_eq!(message, MESSAGE);
+
+            bob_connection.send(&42u32).await.unwrap();
         });
 
         let alice_connection: PlainConnection =
             TcpStream::connect(bob_address).await.unwrap().into();
 
-        let mut alice_connection = alice_connection.secure().await.unwrap();
-
-        alice_connection
-            .authenticate(&alice_keychain)
+        let (mut alice_connection, remote_keycard) = alice_connection
+            .initiate_handshake(&alice_keychain)
             .await
             .unwrap();
 
-        alice_connection
-            .send_plain(&String::from(MESSAGE))
-            .await
+        assert_eq!(remote_keycard, bob_keycard);
+
+        alice_connection.send(&String::from(MESSAGE)).await.unwrap();
+
+        let message: u32 = alice_connection.receive().await.unwrap();
+        assert_eq!(message, 42);
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn handshake_impersonation() {
+        let alice_keychain = KeyChain::random();
+        let bob_keycard = KeyChain::random().keycard();
+        let eve_keychain = KeyChain::random();
+
+        let (eve_listener, eve_address) = new_listener().await;
+
+        let eve_task = tokio::spawn(async move {
+            let mut eve_connection: PlainConnection = eve_listener.accept().await.unwrap().0.into();
+
+            // Eve presents Bob's `KeyCard`, but can only sign with her own `KeyChain`
+
+            let remote: Ephemeral = eve_connection.receive().await.unwrap();
+
+            let keypair = KeyPair::random();
+            let local = Ephemeral::new(keypair.public(), &eve_connection);
+
+            let (shared_key, role) = keypair.exchange(remote.key);
+
+            let (mut handshake_sender, _) = channel::channel(
+                shared_key.derive(
+                    HANDSHAKE_KEY_CONTEXT,
+                    &transcript(&remote, &local, &[]).to_bytes(),
+                ),
+                role,
+            );
+
+            let signature = eve_keychain
+                .sign(&HandshakeTranscript {
+                    role: HandshakeRole::Responder,
+                    transcript: transcript(&remote, &local, &[bob_keycard.identity()]),
+                })
+                .unwrap();
+
+            let proof = handshake_sender
+                .encrypt(&HandshakeProof {
+                    keycard: bob_keycard,
+                    signature,
+                })
+                .unwrap();
+
+            eve_connection.send(&local).await.unwrap();
+            eve_connection.send_bytes(&proof).await.unwrap();
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(eve_address).await.unwrap().into();
+
+        assert!(alice_connection
+            .initiate_handshake(&alice_keychain)
+            .await
+            .is_err());
+
+        eve_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume() {
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let alice_identity = alice_keychain.identity();
+        let bob_identity = bob_keychain.identity();
+
+        let ticket_key = TicketKey::random();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let (mut bob_connection, remote_keycard) = bob_connection
+                .accept_handshake(&bob_keychain)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                bob_connection.remote_identity(),
+                Some(remote_keycard.identity())
+            );
+
+            bob_connection
+                .issue_ticket(&ticket_key, Duration::from_secs(3600))
+                .await
+                .unwrap();
+
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let (mut bob_connection, remote) =
+                bob_connection.accept_resumption(&ticket_key).await.unwrap();
+
+            assert_eq!(remote, alice_identity);
+
+            let message: u32 = bob_connection.receive().await.unwrap();
+            assert_eq!(message, 42);
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let (mut alice_connection, _) = alice_connection
+            .initiate_handshake(&alice_keychain)
+            .await
+            .unwrap();
+
+        let ticket = alice_connection.receive_ticket().await.unwrap();
+        assert_eq!(ticket.server(), bob_identity);
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let mut alice_connection = alice_connection.resume(&ticket).await.unwrap();
+        alice_connection.send(&42u32).await.unwrap();
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume_replayed() {
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let bob_identity = bob_keychain.identity();
+
+        let ticket_key = TicketKey::random();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let (mut bob_connection, _) = bob_connection
+                .accept_handshake(&bob_keychain)
+                .await
+                .unwrap();
+
+            bob_connection
+                .issue_ticket(&ticket_key, Duration::from_secs(3600))
+                .await
+                .unwrap();
+
+            // A ticket observed on the wire is useless without its pre-shared key..
+
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+            assert!(bob_connection.accept_resumption(&ticket_key).await.is_err());
+
+            // .. and cannot be used again once it was opened
+
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+            assert!(bob_connection.accept_resumption(&ticket_key).await.is_err());
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let (mut alice_connection, _) = alice_connection
+            .initiate_handshake(&alice_keychain)
+            .await
+            .unwrap();
+
+        let ticket = alice_connection.receive_ticket().await.unwrap();
+
+        let forged = Ticket::new(
+            bob_identity,
+            ticket.ticket().to_vec(),
+            [0u8; PSK_LENGTH],
+            ticket.expiry(),
+        );
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        assert!(alice_connection.resume(&forged).await.is_err());
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        assert!(alice_connection.resume(&ticket).await.is_err());
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn resume_rejected() {
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let bob_identity = bob_keychain.identity();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let (mut bob_connection, _) = bob_connection
+                .accept_handshake(&bob_keychain)
+                .await
+                .unwrap();
+
+            bob_connection
+                .issue_ticket(&TicketKey::random(), Duration::from_secs(3600))
+                .await
+                .unwrap();
+
+            // Tickets issued under a different `TicketKey` are rejected
+
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            assert!(bob_connection
+                .accept_resumption(&TicketKey::random())
+                .await
+                .is_err());
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let (mut alice_connection, _) = alice_connection
+            .initiate_handshake(&alice_keychain)
+            .await
+            .unwrap();
+
+        let ticket = alice_connection.receive_ticket().await.unwrap();
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        assert!(alice_connection.resume(&ticket).await.is_err());
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn message_too_large() {
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let (mut bob_connection, _) = bob_connection
+                .accept_handshake(&bob_keychain)
+                .await
+                .unwrap();
+
+            bob_connection.configure(ConnectionSettings {
+                max_message_size: 1024,
+                ..Default::default()
+            });
+
+            match bob_connection.receive::<Vec<u8>>().await.unwrap_err().top() {
+                SecureConnectionError::MessageTooLarge { .. } => (),
+                error => panic!("unexpected error upon receiving large message: {}", error),
+            }
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let (mut alice_connection, _) = alice_connection
+            .initiate_handshake(&alice_keychain)
+            .await
+            .unwrap();
+
+        alice_connection.send(&vec![0u8; 4096]).await.unwrap();
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn channel_settings() {
+        let settings = || ConnectionSettings {
+            channel_settings: ChannelSettings {
+                rekey_messages: 2,
+                rekey_bytes: u64::MAX,
+            },
+            ..Default::default()
+        };
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let mut bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            bob_connection.configure(settings());
+            let mut bob_connection = bob_connection.secure().await.unwrap();
+
+            for expected in 0..8u32 {
+                let message: u32 = bob_connection.receive().await.unwrap();
+                assert_eq!(message, expected);
+            }
+        });
+
+        let mut alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        alice_connection.configure(settings());
+        let mut alice_connection = alice_connection.secure().await.unwrap();
+
+        for message in 0..8u32 {
+            alice_connection.send(&message).await.unwrap();
+        }
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn channel_settings_mismatch() {
+        let settings = |rekey_messages| ConnectionSettings {
+            channel_settings: ChannelSettings {
+                rekey_messages,
+                rekey_bytes: u64::MAX,
+            },
+            ..Default::default()
+        };
+
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let mut bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            bob_connection.configure(settings(3));
+
+            let (mut bob_connection, _) = bob_connection
+                .accept_handshake(&bob_keychain)
+                .await
+                .unwrap();
+
+            for expected in 0..8u32 {
+                let message: u32 = bob_connection.receive().await.unwrap();
+                assert_eq!(message, expected);
+
+                bob_connection.send(&message).await.unwrap();
+            }
+        });
+
+        let mut alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        alice_connection.configure(settings(5));
+
+        let (mut alice_connection, _) = alice_connection
+            .initiate_handshake(&alice_keychain)
+            .await
+            .unwrap();
+
+        // Both ends ratchet every 3 messages
+        for message in 0..8u32 {
+            alice_connection.send(&message).await.unwrap();
+
+            let echo: u32 = alice_connection.receive().await.unwrap();
+            assert_eq!(echo, message);
+        }
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn single_send() {
+        const MESSAGE: &str = "Hello Bob, this is Alice!";
+
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let mut bob_connection = bob_connection.secure().await.unwrap();
+
+            bob_connection.authenticate(&bob_keychain).await.unwrap();
+            let message: String = bob_connection.receive().await.unwrap();
+
+            assert_eq!(message, MESSAGE);
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let mut alice_connection = alice_connection.secure().await.unwrap();
+
+        alice_connection
+            .authenticate(&alice_keychain)
+            .await
+            .unwrap();
+
+        alice_connection.send(&String::from(MESSAGE)).await.unwrap();
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn single_send_plain() {
+        const MESSAGE: &str = "Hello Bob, this is Alice!";
+
+        let alice_keychain = KeyChain::random();
+        let bob_keychain = KeyChain::random();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+
+            let mut bob_connection = bob_connection.secure().await.unwrap();
+
+            bob_connection.authenticate(&bob_keychain).await.unwrap();
+            let message: String = bob_connection.receive_plain().await.unwrap();
+
+            assert_eq!(message, MESSAGE);
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+
+        let mut alice_connection = alice_connection.secure().await.unwrap();
+
+        alice_connection
+            .authenticate(&alice_keychain)
+            .await
+            .unwrap();
+
+        alice_connection
+            .send_plain(&String::from(MESSAGE))
+            .await
             .unwrap();
 
         bob_task.await.unwrap();
@@ -496,4 +1633,103 @@ mod tests {
 
         bob_task.await.unwrap();
     }
+
+    #[tokio::test]
+    async fn send_many() {
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+            let mut bob_connection = bob_connection.secure().await.unwrap();
+
+            for expected in 0..33u32 {
+                let message: u32 = bob_connection.receive().await.unwrap();
+                assert_eq!(message, expected);
+            }
+
+            let message: Vec<u8> = bob_connection.receive_plain_bytes().await.unwrap();
+            assert_eq!(message, b"abc");
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+        let mut alice_connection = alice_connection.secure().await.unwrap();
+
+        let messages = (0..32u32).collect::<Vec<_>>();
+        alice_connection.send_many(&messages).await.unwrap();
+
+        alice_connection.feed(&32u32).unwrap();
+        alice_connection.feed_plain_bytes(b"abc");
+        alice_connection.flush().await.unwrap();
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stream() {
+        let data = (0..(1 << 20) + 17)
+            .map(|index| (index % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let expected = data.clone();
+
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+            let mut bob_connection = bob_connection.secure().await.unwrap();
+
+            let mut received = Vec::new();
+
+            bob_connection
+                .receive_stream()
+                .read_to_end(&mut received)
+                .await
+                .unwrap();
+
+            assert_eq!(received, expected);
+
+            let message: u32 = bob_connection.receive().await.unwrap();
+            assert_eq!(message, 42);
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+        let mut alice_connection = alice_connection.secure().await.unwrap();
+
+        let length = alice_connection.send_stream(&data[..]).await.unwrap();
+        assert_eq!(length, data.len() as u64);
+
+        alice_connection.send(&42u32).await.unwrap();
+
+        bob_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stream_truncated() {
+        let (bob_listener, bob_address) = new_listener().await;
+
+        let bob_task = tokio::spawn(async move {
+            let bob_connection: PlainConnection = bob_listener.accept().await.unwrap().0.into();
+            let mut bob_connection = bob_connection.secure().await.unwrap();
+
+            let mut received = Vec::new();
+
+            assert!(bob_connection
+                .receive_stream()
+                .read_to_end(&mut received)
+                .await
+                .is_err());
+        });
+
+        let alice_connection: PlainConnection =
+            TcpStream::connect(bob_address).await.unwrap().into();
+        let mut alice_connection = alice_connection.secure().await.unwrap();
+
+        // A data frame that is never followed by an end-of-stream frame
+        alice_connection.send_bytes(&[0, 1, 2, 3]).await.unwrap();
+        drop(alice_connection);
+
+        bob_task.await.unwrap();
+    }
 }
@@ -1,10 +1,13 @@
 use crate::{
     crypto::primitives::channel::Receiver as ChannelReceiver,
-    net::{ReceiverSettings, SecureConnectionError, UnitReceiver},
+    net::{
+        ReceiveStream, ReceiverSettings, SecureConnectionError, UnitReceiver, UnitReceiverError,
+    },
     time,
 };
 use doomstack::{here, Doom, ResultExt, Top};
 use serde::de::DeserializeOwned;
+use std::cmp;
 
 pub struct SecureReceiver {
     unit_receiver: UnitReceiver,
@@ -29,6 +32,17 @@ impl SecureReceiver {
         self.settings = settings;
     }
 
+    pub(in crate::net) fn restrict_message_size(&mut self, limit: usize) -> usize {
+        let max_message_size = self.settings.max_message_size;
+        self.settings.max_message_size = cmp::min(max_message_size, limit);
+
+        max_message_size
+    }
+
+    pub(in crate::net) fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.settings.max_message_size = max_message_size;
+    }
+
     pub fn free_buffer(&mut self) {
         self.unit_receiver.free_buffer();
     }
@@ -92,12 +106,34 @@ impl SecureReceiver {
         Ok(self.unit_receiver.as_vec().clone())
     }
 
+    /// Receives a stream sent by `SecureSender::send_stream`.
+    pub fn receive_stream(&mut self) -> ReceiveStream<'_> {
+        ReceiveStream::new(self)
+    }
+
     async fn receive_unit(&mut self) -> Result<(), Top<SecureConnectionError>> {
-        time::optional_timeout(self.settings.receive_timeout, self.unit_receiver.receive())
-            .await
-            .pot(SecureConnectionError::ReceiveTimeout, here!())?
-            .map_err(SecureConnectionError::read_failed)
-            .map_err(Doom::into_top)
-            .spot(here!())
+        let result = time::optional_timeout(
+            self.settings.receive_timeout,
+            self.unit_receiver.receive(self.settings.max_message_size),
+        )
+        .await
+        .pot(SecureConnectionError::ReceiveTimeout, here!())?;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(UnitReceiverError::MessageTooLarge { size }) => {
+                SecureConnectionError::MessageTooLarge {
+                    size,
+                    max_message_size: self.settings.max_message_size,
+                }
+                .fail()
+                .spot(here!())
+            }
+            Err(UnitReceiverError::ReadFailed(error)) => {
+                Err(SecureConnectionError::read_failed(error))
+                    .map_err(Doom::into_top)
+                    .spot(here!())
+            }
+        }
     }
 }
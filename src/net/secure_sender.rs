@@ -1,10 +1,14 @@
 use crate::{
     crypto::primitives::channel::Sender as ChannelSender,
-    net::{SecureConnectionError, SenderSettings, UnitSender},
+    net::{
+        receive_stream::{ABORT_TAG, DATA_TAG, END_TAG, STREAM_CHUNK_SIZE},
+        SecureConnectionError, SenderSettings, UnitSender,
+    },
     time,
 };
 use doomstack::{here, Doom, ResultExt, Top};
 use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
 pub struct SecureSender {
     unit_sender: UnitSender,
@@ -34,6 +38,70 @@ impl SecureSender {
     }
 
     pub async fn send<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.feed(message)?;
+        self.flush().await
+    }
+
+    pub async fn send_bytes(&mut self, message: &[u8]) -> Result<(), Top<SecureConnectionError>> {
+        self.feed_bytes(message);
+        self.flush().await
+    }
+
+    pub async fn send_plain<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.feed_plain(message)?;
+        self.flush().await
+    }
+
+    pub async fn send_plain_bytes(
+        &mut self,
+        message: &[u8],
+    ) -> Result<(), Top<SecureConnectionError>> {
+        self.feed_plain_bytes(message);
+        self.flush().await
+    }
+
+    pub async fn send_raw<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: Serialize,
+    {
+        self.feed_raw(message)?;
+        self.flush().await
+    }
+
+    pub async fn send_raw_bytes(
+        &mut self,
+        message: &[u8],
+    ) -> Result<(), Top<SecureConnectionError>> {
+        self.feed_raw_bytes(message);
+        self.flush().await
+    }
+
+    /// Sends all `messages` using as few writes as possible.
+    pub async fn send_many<'m, M, I>(
+        &mut self,
+        messages: I,
+    ) -> Result<(), Top<SecureConnectionError>>
+    where
+        M: 'm + Serialize,
+        I: IntoIterator<Item = &'m M>,
+    {
+        for message in messages {
+            self.feed(message)?;
+        }
+
+        self.flush().await
+    }
+
+    /// Queues `message` for sending, without writing it to the underlying
+    /// socket. Queued messages are sent, in order, by the next `flush`
+    /// (or by the next `send`).
+    pub fn feed<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
     where
         M: Serialize,
     {
@@ -41,17 +109,18 @@ impl SecureSender {
             .encrypt_into(message, self.unit_sender.as_vec())
             .pot(SecureConnectionError::EncryptFailed, here!())?;
 
-        self.send_unit().await
+        self.unit_sender.feed();
+        Ok(())
     }
 
-    pub async fn send_bytes(&mut self, message: &[u8]) -> Result<(), Top<SecureConnectionError>> {
+    pub fn feed_bytes(&mut self, message: &[u8]) {
         self.channel_sender
             .encrypt_bytes_into(message, self.unit_sender.as_vec());
 
-        self.send_unit().await
+        self.unit_sender.feed();
     }
 
-    pub async fn send_plain<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    pub fn feed_plain<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
     where
         M: Serialize,
     {
@@ -59,20 +128,18 @@ impl SecureSender {
             .authenticate_into(message, self.unit_sender.as_vec())
             .pot(SecureConnectionError::MacComputeFailed, here!())?;
 
-        self.send_unit().await
+        self.unit_sender.feed();
+        Ok(())
     }
 
-    pub async fn send_plain_bytes(
-        &mut self,
-        message: &[u8],
-    ) -> Result<(), Top<SecureConnectionError>> {
+    pub fn feed_plain_bytes(&mut self, message: &[u8]) {
         self.channel_sender
             .authenticate_bytes_into(message, self.unit_sender.as_vec());
 
-        self.send_unit().await
+        self.unit_sender.feed();
     }
 
-    pub async fn send_raw<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
+    pub fn feed_raw<M>(&mut self, message: &M) -> Result<(), Top<SecureConnectionError>>
     where
         M: Serialize,
     {
@@ -81,19 +148,22 @@ impl SecureSender {
             .map_err(Doom::into_top)
             .spot(here!())?;
 
-        self.send_unit().await
+        self.unit_sender.feed();
+        Ok(())
     }
 
-    pub async fn send_raw_bytes(
-        &mut self,
-        message: &[u8],
-    ) -> Result<(), Top<SecureConnectionError>> {
+    pub fn feed_raw_bytes(&mut self, message: &[u8]) {
         self.unit_sender.as_vec().clear();
         self.unit_sender.as_vec().extend_from_slice(message);
-        self.send_unit().await
+        self.unit_sender.feed();
     }
 
-    async fn send_unit(&mut self) -> Result<(), Top<SecureConnectionError>> {
+    /// Writes all queued messages to the underlying socket.
+    ///
+    /// If a `flush` fails or times out, the remote end might have received
+    /// only part of the queued messages: all further `flush`es (and `send`s)
+    /// fail, and the connection should be dropped.
+    pub async fn flush(&mut self) -> Result<(), Top<SecureConnectionError>> {
         time::optional_timeout(self.settings.send_timeout, self.unit_sender.flush())
             .await
             .pot(SecureConnectionError::SendTimeout, here!())?
@@ -101,4 +171,48 @@ impl SecureSender {
             .map_err(Doom::into_top)
             .spot(here!())
     }
+
+    /// Sends the contents of `stream`, until its end, as a sequence of
+    /// encrypted frames, returning the number of bytes sent. The remote end
+    /// must read them using `SecureReceiver::receive_stream`.
+    ///
+    /// `stream` is only read as fast as frames are sent. If reading `stream`
+    /// fails, the remote end is notified that the stream was aborted, and a
+    /// `StreamFailed` error variant is returned.
+    pub async fn send_stream<R>(&mut self, mut stream: R) -> Result<u64, Top<SecureConnectionError>>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut frame = vec![0u8; STREAM_CHUNK_SIZE + 1];
+        frame[0] = DATA_TAG;
+
+        let mut length = 0;
+
+        loop {
+            let size = match stream.read(&mut frame[1..]).await {
+                Ok(size) => size,
+                Err(error) => {
+                    let _ = self.send_bytes(&[ABORT_TAG]).await;
+
+                    return Err(SecureConnectionError::stream_failed(error))
+                        .map_err(Doom::into_top)
+                        .spot(here!());
+                }
+            };
+
+            if size == 0 {
+                break;
+            }
+
+            self.send_bytes(&frame[..size + 1]).await?;
+            length += size as u64;
+        }
+
+        let mut end = vec![END_TAG];
+        end.extend_from_slice(&length.to_le_bytes());
+
+        self.send_bytes(&end).await?;
+
+        Ok(length)
+    }
 }
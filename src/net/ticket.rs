@@ -0,0 +1,255 @@
+use crate::crypto::{
+    primitives::{
+        channel,
+        exchange::{Role, SharedKey},
+    },
+    Identity,
+};
+use doomstack::{here, Doom, ResultExt, Top};
+use parking_lot::Mutex;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::SystemTime};
+use zeroize::Zeroize;
+
+pub(in crate::net) const PSK_LENGTH: usize = 32;
+
+const NONCE_LENGTH: usize = 32;
+
+// `blake3` key derivation context, used to derive a fresh key for every ticket
+const TICKET_KEY_CONTEXT: &str = "talk 2022-06-01 TicketKey::seal";
+
+/// A resumption ticket, held by the client of a `SecureConnection`.
+///
+/// A `Ticket` allows a later connection to `server()` to be secured
+/// and authenticated without exchanging `KeyCard`s or signatures (see
+/// `PlainConnection::resume`). Its pre-shared key is wiped from memory
+/// when the `Ticket` is dropped.
+#[derive(Clone)]
+pub struct Ticket {
+    server: Identity,
+    ticket: Vec<u8>,
+    psk: [u8; PSK_LENGTH],
+    expiry: SystemTime,
+}
+
+/// The key a server uses to encrypt the resumption tickets it issues.
+///
+/// Tickets are only valid under the `TicketKey` that issued them: to
+/// invalidate all outstanding tickets, it is sufficient to drop it.
+/// Every ticket can be opened only once: a `TicketKey` remembers the
+/// tickets it opened until they expire, and rejects replays.
+pub struct TicketKey {
+    key: SharedKey,
+    used: Mutex<HashMap<[u8; NONCE_LENGTH], SystemTime>>,
+}
+
+// What a server learns from a ticket: `ticket` in `Ticket` is an
+// encrypted `TicketContents`, which only the issuing `TicketKey` can open
+#[derive(Serialize, Deserialize)]
+pub(in crate::net) struct TicketContents {
+    pub client: Identity,
+    pub psk: [u8; PSK_LENGTH],
+    pub expiry: SystemTime,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SealedTicket {
+    nonce: [u8; NONCE_LENGTH],
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Doom)]
+pub(in crate::net) enum TicketError {
+    #[doom(description("Ticket is expired"))]
+    Expired,
+    #[doom(description("Failed to open ticket"))]
+    OpenFailed,
+    #[doom(description("Ticket was already used"))]
+    Replayed,
+}
+
+impl Ticket {
+    pub(in crate::net) fn new(
+        server: Identity,
+        ticket: Vec<u8>,
+        psk: [u8; PSK_LENGTH],
+        expiry: SystemTime,
+    ) -> Self {
+        Ticket {
+            server,
+            ticket,
+            psk,
+            expiry,
+        }
+    }
+
+    pub fn server(&self) -> Identity {
+        self.server
+    }
+
+    pub fn expiry(&self) -> SystemTime {
+        self.expiry
+    }
+
+    pub fn is_expired(&self) -> bool {
+        SystemTime::now() >= self.expiry
+    }
+
+    pub(in crate::net) fn ticket(&self) -> &[u8] {
+        self.ticket.as_slice()
+    }
+
+    pub(in crate::net) fn psk(&self) -> &[u8; PSK_LENGTH] {
+        &self.psk
+    }
+}
+
+impl TicketContents {
+    pub fn random(client: Identity, expiry: SystemTime) -> Self {
+        let mut psk = [0u8; PSK_LENGTH];
+        OsRng.fill_bytes(&mut psk);
+
+        TicketContents {
+            client,
+            psk,
+            expiry,
+        }
+    }
+}
+
+impl TicketKey {
+    pub fn random() -> Self {
+        TicketKey {
+            key: SharedKey::random(),
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(in crate::net) fn seal(&self, contents: &TicketContents) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+
+        // Every ticket is encrypted under its own key, so that the
+        // channel's (deterministic) nonces are never reused
+        let (mut sender, _) =
+            channel::channel(self.key.derive(TICKET_KEY_CONTEXT, &nonce), Role::Even);
+        let ciphertext = sender.encrypt(contents).unwrap();
+
+        bincode::serialize(&SealedTicket { nonce, ciphertext }).unwrap()
+    }
+
+    pub(in crate::net) fn open(&self, ticket: &[u8]) -> Result<TicketContents, Top<TicketError>> {
+        let sealed: SealedTicket = bincode::deserialize(ticket)
+            .map_err(|_| TicketError::OpenFailed.into_top())
+            .spot(here!())?;
+
+        let (_, mut receiver) = channel::channel(
+            self.key.derive(TICKET_KEY_CONTEXT, &sealed.nonce),
+            Role::Odd,
+        );
+
+        let contents: TicketContents = receiver
+            .decrypt(&sealed.ciphertext)
+            .pot(TicketError::OpenFailed, here!())?;
+
+        let now = SystemTime::now();
+
+        if now >= contents.expiry {
+            return TicketError::Expired.fail().spot(here!());
+        }
+
+        // Expired tickets are rejected anyway, and need not be remembered
+        let mut used = self.used.lock();
+        used.retain(|_, expiry| *expiry > now);
+
+        if used.insert(sealed.nonce, contents.expiry).is_some() {
+            return TicketError::Replayed.fail().spot(here!());
+        }
+
+        Ok(contents)
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.psk.zeroize();
+    }
+}
+
+impl Drop for TicketContents {
+    fn drop(&mut self) {
+        self.psk.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyChain;
+    use std::time::Duration;
+
+    fn contents(lifetime: Duration) -> TicketContents {
+        TicketContents {
+            client: KeyChain::random().identity(),
+            psk: [42u8; PSK_LENGTH],
+            expiry: SystemTime::now() + lifetime,
+        }
+    }
+
+    #[test]
+    fn correct() {
+        let key = TicketKey::random();
+        let contents = contents(Duration::from_secs(3600));
+
+        let ticket = key.seal(&contents);
+        let opened = key.open(&ticket).unwrap();
+
+        assert_eq!(opened.client, contents.client);
+        assert_eq!(opened.psk, contents.psk);
+        assert_eq!(opened.expiry, contents.expiry);
+    }
+
+    #[test]
+    fn expired() {
+        let key = TicketKey::random();
+        let ticket = key.seal(&contents(Duration::from_secs(0)));
+
+        match key.open(&ticket).unwrap_err().top() {
+            TicketError::Expired => (),
+            error => panic!("unexpected error upon opening expired ticket: {}", error),
+        }
+    }
+
+    #[test]
+    fn replayed() {
+        let key = TicketKey::random();
+        let ticket = key.seal(&contents(Duration::from_secs(3600)));
+
+        key.open(&ticket).unwrap();
+
+        match key.open(&ticket).unwrap_err().top() {
+            TicketError::Replayed => (),
+            error => panic!("unexpected error upon opening replayed ticket: {}", error),
+        }
+    }
+
+    #[test]
+    fn wrong_key() {
+        let key = TicketKey::random();
+        let ticket = key.seal(&contents(Duration::from_secs(3600)));
+
+        assert!(TicketKey::random().open(&ticket).is_err());
+    }
+
+    #[test]
+    fn compromise() {
+        let key = TicketKey::random();
+
+        let mut ticket = key.seal(&contents(Duration::from_secs(3600)));
+        let last = ticket.len() - 1;
+        ticket[last] = ticket[last].wrapping_add(1);
+
+        assert!(key.open(&ticket).is_err());
+    }
+}
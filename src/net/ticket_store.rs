@@ -0,0 +1,130 @@
+use crate::{
+    crypto::Identity,
+    net::{Ticket, TicketStoreSettings},
+};
+use std::collections::HashMap;
+
+/// A bounded collection of resumption `Ticket`s, indexed by server `Identity`.
+///
+/// At most one `Ticket` is held per server. When `capacity` is reached,
+/// expired `Ticket`s are evicted first, then the `Ticket` that expires
+/// soonest. A `capacity` of 0 disables the store.
+pub struct TicketStore {
+    tickets: HashMap<Identity, Ticket>,
+    settings: TicketStoreSettings,
+}
+
+impl TicketStore {
+    pub fn new(settings: TicketStoreSettings) -> Self {
+        TicketStore {
+            tickets: HashMap::new(),
+            settings,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tickets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tickets.is_empty()
+    }
+
+    pub fn insert(&mut self, ticket: Ticket) {
+        if self.settings.capacity == 0 || ticket.is_expired() {
+            return;
+        }
+
+        if !self.tickets.contains_key(&ticket.server())
+            && self.tickets.len() >= self.settings.capacity
+        {
+            self.tickets.retain(|_, ticket| !ticket.is_expired());
+
+            if self.tickets.len() >= self.settings.capacity {
+                let soonest = self
+                    .tickets
+                    .iter()
+                    .min_by_key(|(_, ticket)| ticket.expiry())
+                    .map(|(server, _)| *server)
+                    .unwrap(); // `capacity > 0`, hence `tickets` is non-empty
+
+                self.tickets.remove(&soonest);
+            }
+        }
+
+        self.tickets.insert(ticket.server(), ticket);
+    }
+
+    /// Removes and returns the `Ticket` held for `server`, if any
+    /// (`Ticket`s are meant to be used only once).
+    pub fn take(&mut self, server: Identity) -> Option<Ticket> {
+        self.tickets
+            .remove(&server)
+            .filter(|ticket| !ticket.is_expired())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyChain;
+    use std::time::{Duration, SystemTime};
+
+    fn ticket(lifetime: Duration) -> Ticket {
+        Ticket::new(
+            KeyChain::random().identity(),
+            Vec::new(),
+            Default::default(),
+            SystemTime::now() + lifetime,
+        )
+    }
+
+    #[test]
+    fn take() {
+        let mut store = TicketStore::new(Default::default());
+
+        let ticket = ticket(Duration::from_secs(3600));
+        let server = ticket.server();
+
+        store.insert(ticket);
+
+        assert_eq!(store.take(server).unwrap().server(), server);
+        assert!(store.take(server).is_none());
+    }
+
+    #[test]
+    fn bounded() {
+        let mut store = TicketStore::new(TicketStoreSettings { capacity: 4 });
+
+        let soonest = ticket(Duration::from_secs(60));
+        let soonest_server = soonest.server();
+
+        store.insert(soonest);
+
+        let servers = (0..4)
+            .map(|_| {
+                let ticket = ticket(Duration::from_secs(3600));
+                let server = ticket.server();
+
+                store.insert(ticket);
+                server
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(store.len(), 4);
+        assert!(store.take(soonest_server).is_none());
+
+        for server in servers {
+            assert!(store.take(server).is_some());
+        }
+    }
+
+    #[test]
+    fn disabled() {
+        let mut store = TicketStore::new(TicketStoreSettings { capacity: 0 });
+
+        store.insert(ticket(Duration::from_secs(3600)));
+
+        assert!(store.is_empty());
+    }
+}
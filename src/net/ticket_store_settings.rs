@@ -0,0 +1,10 @@
+#[derive(Debug, Clone)]
+pub struct TicketStoreSettings {
+    pub capacity: usize,
+}
+
+impl Default for TicketStoreSettings {
+    fn default() -> Self {
+        TicketStoreSettings { capacity: 1024 }
+    }
+}
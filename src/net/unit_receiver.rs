@@ -10,6 +10,11 @@ pub(in crate::net) struct UnitReceiver {
     buffer: Vec<u8>,
 }
 
+pub(in crate::net) enum UnitReceiverError {
+    MessageTooLarge { size: usize },
+    ReadFailed(io::Error),
+}
+
 impl UnitReceiver {
     pub fn new(read_half: ReadHalf<Box<dyn Socket>>) -> Self {
         UnitReceiver {
@@ -34,10 +39,34 @@ impl UnitReceiver {
         mem::take(&mut self.buffer);
     }
 
-    pub async fn receive(&mut self) -> io::Result<()> {
-        let size = self.receive_size().await?;
-        self.buffer.resize(size, 0);
-        self.read_half.read_exact(&mut self.buffer[..]).await?;
+    // If the announced size exceeds `max_message_size`, the message is left
+    // unread: the underlying stream is no longer aligned to message
+    // boundaries, and should be dropped
+    pub async fn receive(&mut self, max_message_size: usize) -> Result<(), UnitReceiverError> {
+        let size = self
+            .receive_size()
+            .await
+            .map_err(UnitReceiverError::ReadFailed)?;
+
+        if size > max_message_size {
+            return Err(UnitReceiverError::MessageTooLarge { size });
+        }
+
+        // `buffer` grows as bytes are received (rather than as soon as `size` is
+        // known), so that a remote cannot make us allocate memory it does not fill
+        self.buffer.clear();
+
+        (&mut self.read_half)
+            .take(size as u64)
+            .read_to_end(&mut self.buffer)
+            .await
+            .map_err(UnitReceiverError::ReadFailed)?;
+
+        if self.buffer.len() < size {
+            return Err(UnitReceiverError::ReadFailed(
+                io::ErrorKind::UnexpectedEof.into(),
+            ));
+        }
 
         Ok(())
     }
@@ -48,3 +77,63 @@ impl UnitReceiver {
         Ok(u32::from_le_bytes(size) as usize)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::AsyncWriteExt,
+        net::{TcpListener, TcpStream},
+    };
+
+    async fn setup() -> (UnitReceiver, TcpStream) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let remote = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let socket: Box<dyn Socket> = Box::new(listener.accept().await.unwrap().0);
+        let (read_half, _) = io::split(socket);
+
+        (UnitReceiver::new(read_half), remote)
+    }
+
+    #[tokio::test]
+    async fn correct() {
+        let (mut receiver, mut remote) = setup().await;
+
+        remote.write_all(&3u32.to_le_bytes()).await.unwrap();
+        remote.write_all(b"abc").await.unwrap();
+
+        assert!(receiver.receive(3).await.is_ok());
+        assert_eq!(receiver.as_slice(), b"abc");
+    }
+
+    #[tokio::test]
+    async fn too_large() {
+        let (mut receiver, mut remote) = setup().await;
+
+        remote.write_all(&u32::MAX.to_le_bytes()).await.unwrap();
+
+        match receiver.receive(1 << 16).await {
+            Err(UnitReceiverError::MessageTooLarge { size }) => assert_eq!(size, u32::MAX as usize),
+            _ => panic!("unexpected outcome upon receiving an oversized message"),
+        }
+
+        assert_eq!(receiver.as_vec().capacity(), 0);
+    }
+
+    #[tokio::test]
+    async fn truncated() {
+        let (mut receiver, mut remote) = setup().await;
+
+        remote.write_all(&16u32.to_le_bytes()).await.unwrap();
+        remote.write_all(b"abc").await.unwrap();
+        drop(remote);
+
+        assert!(matches!(
+            receiver.receive(16).await,
+            Err(UnitReceiverError::ReadFailed(_))
+        ));
+    }
+}
@@ -1,13 +1,27 @@
 use crate::net::Socket;
-use std::mem;
+use std::{io::IoSlice, mem};
 use tokio::{
     io,
     io::{AsyncWriteExt, WriteHalf},
 };
 
+// Maximum number of units passed to a single vectored write
+const MAX_UNITS_PER_WRITE: usize = 512;
+
+// Units are encoded in `buffer`, then moved to `units` by `feed`, along
+// with their size. `flush` writes all units at once, each prefixed with
+// its size, using vectored writes: any number of units can be sent using
+// a single write, without copying their payloads.
+//
+// If a `flush` fails (or is dropped, e.g., upon timing out), the remote
+// end might have received only part of the units: all further `flush`es
+// fail, as the stream of units can no longer be resumed.
 pub(in crate::net) struct UnitSender {
     write_half: WriteHalf<Box<dyn Socket>>,
     buffer: Vec<u8>,
+    sizes: Vec<[u8; 4]>,
+    units: Vec<Vec<u8>>,
+    poisoned: bool,
 }
 
 impl UnitSender {
@@ -15,6 +29,9 @@ impl UnitSender {
         UnitSender {
             write_half,
             buffer: Vec::new(),
+            sizes: Vec::new(),
+            units: Vec::new(),
+            poisoned: false,
         }
     }
 
@@ -28,18 +45,173 @@ impl UnitSender {
 
     pub fn free_buffer(&mut self) {
         mem::take(&mut self.buffer);
+
+        // Units fed but not yet flushed must not be lost
+        if self.units.is_empty() {
+            mem::take(&mut self.sizes);
+            mem::take(&mut self.units);
+        }
+    }
+
+    pub fn feed(&mut self) {
+        let size = (self.buffer.len() as u32).to_le_bytes();
+
+        self.sizes.push(size);
+        self.units.push(mem::take(&mut self.buffer));
     }
 
     pub async fn flush(&mut self) -> io::Result<()> {
-        self.send_size(self.buffer.len()).await?;
-        self.write_half.write_all(&self.buffer).await?;
-        self.buffer.clear();
+        if self.poisoned {
+            self.sizes.clear();
+            self.units.clear();
+
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "a previous flush failed to complete",
+            ));
+        }
+
+        // `poisoned` is reset only once all units are written
+        self.poisoned = true;
+
+        let mut unit = 0; // Index of the first unit not yet fully written
+        let mut offset = 0; // Number of bytes of `unit` (size included) already written
+
+        while unit < self.units.len() {
+            let slices = slices(&self.sizes, &self.units, unit, offset);
+            let mut written = self.write_half.write_vectored(&slices).await?;
+
+            if written == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+
+            while written > 0 {
+                let remaining = 4 + self.units[unit].len() - offset;
+
+                if written >= remaining {
+                    written -= remaining;
+                    unit += 1;
+                    offset = 0;
+                } else {
+                    offset += written;
+                    written = 0;
+                }
+            }
+        }
+
+        // Recycle the allocation of the last unit (e.g., the only unit of a `send`)
+        if let Some(mut last) = self.units.pop() {
+            if self.buffer.capacity() == 0 {
+                last.clear();
+                self.buffer = last;
+            }
+        }
+
+        self.sizes.clear();
+        self.units.clear();
+        self.poisoned = false;
 
         Ok(())
     }
+}
+
+// Returns the slices left to write, starting `offset` bytes into `unit`
+fn slices<'u>(
+    sizes: &'u [[u8; 4]],
+    units: &'u [Vec<u8>],
+    unit: usize,
+    offset: usize,
+) -> Vec<IoSlice<'u>> {
+    let mut slices = Vec::new();
+
+    for (index, (size, payload)) in sizes
+        .iter()
+        .zip(units)
+        .enumerate()
+        .skip(unit)
+        .take(MAX_UNITS_PER_WRITE)
+    {
+        let offset = if index == unit { offset } else { 0 };
+
+        if offset < size.len() {
+            slices.push(IoSlice::new(&size[offset..]));
+            slices.push(IoSlice::new(payload));
+        } else {
+            slices.push(IoSlice::new(&payload[offset - size.len()..]));
+        }
+    }
+
+    slices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::{
+        io::AsyncReadExt,
+        net::{TcpListener, TcpStream},
+        time,
+    };
+
+    #[tokio::test]
+    async fn batch() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let mut remote = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let socket: Box<dyn Socket> = Box::new(listener.accept().await.unwrap().0);
+        let (_, write_half) = io::split(socket);
+
+        let mut sender = UnitSender::new(write_half);
+
+        sender.as_vec().extend_from_slice(b"abc");
+        sender.feed();
+
+        sender.feed(); // Empty unit
+
+        sender.as_vec().extend_from_slice(b"d");
+        sender.feed();
+
+        sender.free_buffer(); // Does not discard fed units
+        sender.flush().await.unwrap();
+
+        let mut received = [0u8; 16];
+        remote.read_exact(&mut received).await.unwrap();
+
+        assert_eq!(&received[..4], &3u32.to_le_bytes());
+        assert_eq!(&received[4..7], b"abc");
+        assert_eq!(&received[7..11], &0u32.to_le_bytes());
+        assert_eq!(&received[11..15], &1u32.to_le_bytes());
+        assert_eq!(&received[15..], b"d");
+    }
+
+    #[tokio::test]
+    async fn flush_timeout() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let _remote = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let socket: Box<dyn Socket> = Box::new(listener.accept().await.unwrap().0);
+        let (_, write_half) = io::split(socket);
+
+        let mut sender = UnitSender::new(write_half);
+
+        // `_remote` never reads: the unit cannot fit the socket buffers
+        sender.as_vec().resize(1 << 26, 0);
+        sender.feed();
+
+        assert!(time::timeout(Duration::from_millis(100), sender.flush())
+            .await
+            .is_err());
+
+        // The remote end holds a partial unit: no further unit can be sent
+        sender.as_vec().extend_from_slice(b"abc");
+        sender.feed();
 
-    async fn send_size(&mut self, size: usize) -> io::Result<()> {
-        let size = (size as u32).to_le_bytes();
-        self.write_half.write_all(&size).await
+        assert!(sender.flush().await.is_err());
+        assert!(sender.units.is_empty());
     }
 }
@@ -1,9 +1,11 @@
 mod unicast {
     use crate::{
+        crypto::{KeyChain, Signed, Statement},
         time::test::join,
         unicast::{test::UnicastSystem, Acknowledgement, PushSettings},
     };
     use futures::stream::{FuturesUnordered, StreamExt};
+    use serde::{Deserialize, Serialize};
 
     #[tokio::test]
     async fn constant_one_to_one_strong() {
@@ -233,4 +235,64 @@ mod unicast {
 
         join([handle]).await.unwrap();
     }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct Note(u32);
+
+    impl Statement for Note {
+        type Header = ();
+        const HEADER: Self::Header = ();
+    }
+
+    #[tokio::test]
+    async fn signed_forward() {
+        let alice = KeyChain::random();
+        let bob = KeyChain::random();
+        let carol = KeyChain::random();
+
+        let alice_keycard = alice.keycard();
+
+        let UnicastSystem {
+            keys,
+            mut senders,
+            mut receivers,
+        } = UnicastSystem::<Signed<Note>>::setup_with_keychains(vec![alice.clone(), bob, carol])
+            .await
+            .into();
+
+        let mut carol_receiver = receivers.remove(2);
+        let mut bob_receiver = receivers.remove(1);
+
+        let bob_sender = senders.remove(1);
+        let alice_sender = senders.remove(0);
+
+        let carol_identity = keys[2];
+
+        let bob_handle = tokio::spawn(async move {
+            let (_, message, acknowledger) = bob_receiver.receive().await;
+            acknowledger.strong();
+
+            // Bob forwards Alice's message without signing it
+            let ack = bob_sender.send(carol_identity, message).await.unwrap();
+            assert_eq!(ack, Acknowledgement::Strong);
+        });
+
+        let carol_handle = tokio::spawn(async move {
+            let (source, message, acknowledger) = carol_receiver.receive().await;
+            acknowledger.strong();
+
+            assert_ne!(source, alice_keycard.identity());
+            assert_eq!(message.signer(), alice_keycard.identity());
+
+            message.verify(&alice_keycard).unwrap();
+            assert_eq!(message.into_statement(), Note(42));
+        });
+
+        let signed = Signed::new(&alice, Note(42)).unwrap();
+
+        let ack = alice_sender.send(keys[1], signed).await.unwrap();
+        assert_eq!(ack, Acknowledgement::Strong);
+
+        join([bob_handle, carol_handle]).await.unwrap();
+    }
 }
@@ -0,0 +1,140 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    DeriveInput, Error, Expr, ExprPath, Ident, Path, Result, Token,
+};
+
+struct Argument {
+    key: Ident,
+    value: Expr,
+}
+
+impl Parse for Argument {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+
+        Ok(Argument { key, value })
+    }
+}
+
+/// Derives `talk::crypto::Statement`.
+///
+/// The scope and header of the `Statement` are set through the `statement`
+/// attribute, e.g. `#[statement(scope = Scope::application("app"), header = Header::Vote)]`.
+/// If `scope` is omitted, the default `Scope::user()` is used. If `header`
+/// is omitted, the header is `()`.
+///
+/// `header` must be a path to a variant of a fieldless enum, defined in the
+/// same crate. Deriving `Statement` for two types with the same `header`
+/// variant fails to compile (with a conflicting implementations error).
+#[proc_macro_derive(Statement, attributes(statement))]
+pub fn derive_statement(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let mut scope = None;
+    let mut header = None;
+
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path.is_ident("statement"))
+    {
+        let arguments =
+            attribute.parse_args_with(Punctuated::<Argument, Token![,]>::parse_terminated)?;
+
+        for argument in arguments {
+            let slot = if argument.key == "scope" {
+                &mut scope
+            } else if argument.key == "header" {
+                &mut header
+            } else {
+                return Err(Error::new_spanned(
+                    argument.key,
+                    "unknown `statement` argument, expected `scope` or `header`",
+                ));
+            };
+
+            if slot.is_some() {
+                return Err(Error::new_spanned(
+                    argument.key,
+                    "duplicate `statement` argument",
+                ));
+            }
+
+            *slot = Some(argument.value);
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let scope = scope.map(|scope| {
+        quote! {
+            const SCOPE: ::talk::crypto::Scope = #scope;
+        }
+    });
+
+    let (header_type, header, claim) = match header {
+        Some(header) => {
+            let header_type = header_type(&header)?;
+
+            // Conflicts with any other claim on the same variant of `header_type`
+            let claim = quote! {
+                impl ::talk::crypto::HeaderClaim<{ #header as usize }> for #header_type {}
+            };
+
+            (quote!(#header_type), quote!(#header), Some(claim))
+        }
+        None => (quote!(()), quote!(()), None),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::talk::crypto::Statement for #name #type_generics #where_clause {
+            #scope
+
+            type Header = #header_type;
+            const HEADER: Self::Header = #header;
+        }
+
+        #claim
+    })
+}
+
+// Strips the variant from `header`, e.g., `Header::Vote` into `Header`
+fn header_type(header: &Expr) -> Result<Path> {
+    if let Expr::Path(ExprPath {
+        qself: None, path, ..
+    }) = header
+    {
+        if path.segments.len() >= 2 {
+            let segments = path
+                .segments
+                .iter()
+                .take(path.segments.len() - 1)
+                .cloned()
+                .collect();
+
+            return Ok(Path {
+                leading_colon: path.leading_colon,
+                segments,
+            });
+        }
+    }
+
+    Err(Error::new_spanned(
+        header,
+        "`header` must be a path to an enum variant (e.g., `Header::Variant`)",
+    ))
+}